@@ -1,17 +1,38 @@
 use std::fs;
+use std::process;
 use crate::commands::Command;
 
 use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::policy::Policy;
 use crate::lexer::lexer::Lexer;
+use crate::optimizer::optimizer::Optimizer;
+use crate::parser::ast::Stmt;
 use crate::parser::parser::Parser;
 use crate::parser::print_ast::AstPrinter;
+use crate::resolver::resolver::Resolver;
+use crate::utils::diagnostic::{render, Diagnostic};
+use crate::utils::error::ResolveError;
 use crate::utils::token::Token;
+use crate::vm::compiler::Compiler;
+use crate::vm::vm::Vm;
+
+/// Exit status for a script with syntax or static errors (`EX_DATAERR`).
+pub const EXIT_COMPILE_ERROR: i32 = 65;
+
+/// Exit status for a script that failed while running (`EX_SOFTWARE`).
+pub const EXIT_RUNTIME_ERROR: i32 = 70;
 
 pub struct Interpret {
     pub filename: String,
     pub tokens: Option<bool>,
     pub ast: Option<bool>,
     pub ast_raw: Option<bool>,
+    /// Optimize the parsed program before running it.
+    pub opt: Option<bool>,
+    /// Run on the bytecode VM instead of the tree-walking interpreter.
+    pub vm: Option<bool>,
+    pub disassemble: Option<bool>,
+    pub policy: Policy,
 }
 
 impl Command for Interpret {
@@ -36,41 +57,89 @@ fn end(name: &str) -> String {
     format!("----------{} END----------\n", name)
 }
 
+/// Prints the lexer's output for `--tokens`.
+pub fn print_tokens(tokens: &[Token]) {
+    println!("{}", begin("TOKENS"));
+    for token in tokens {
+        println!("> {:?}", token);
+    }
+    println!("{}", end("TOKENS"));
+}
+
+/// Prints the parsed program for `--ast`.
+pub fn print_ast(statements: &[Stmt]) {
+    println!("{}", begin("AST"));
+    let mut printer: AstPrinter = AstPrinter::new();
+    printer.print(statements);
+    println!("{}", end("AST"));
+}
+
+/// Prints each error with the source line it points at, then exits.
+fn report(source: &str, filename: &str, errors: &[impl Diagnostic], code: i32) -> ! {
+    for error in errors {
+        eprintln!("{}\n", render(source, filename, error));
+    }
+    process::exit(code);
+}
+
+/// Resolves variable scopes against the `globals` already defined,
+/// reporting static errors, then optimizes the program when `optimize` is
+/// set. The errors are found first so that dropping a dead branch can't
+/// hide one, and the rewritten program is resolved again for the depths of
+/// its names.
+pub fn resolve(
+    statements: Vec<Stmt>,
+    globals: Vec<String>,
+    optimize: bool,
+) -> Result<Vec<Stmt>, Vec<ResolveError>> {
+    Resolver::with_globals(globals.clone()).resolve(&statements)?;
+    if !optimize {
+        return Ok(statements);
+    }
+    let statements = Optimizer::new().optimize(statements);
+    Resolver::with_globals(globals).resolve(&statements)?;
+    Ok(statements)
+}
+
 fn interpret(source: String, props: &Interpret) {
 
     // initialize lexer with source code
-    let mut lexer: Lexer = Lexer::new(source);
+    let mut lexer: Lexer = Lexer::new(source.clone());
 
-    // tokenize source code 
+    // tokenize source code, stopping before the parser if anything was malformed
     let tokens: &Vec<Token> = match lexer.scan_tokens() {
         Ok(tokens) => tokens,
-        Err(e) => {
-            eprintln!("Error scanning tokens: {}", e);
-            return;
-        }
+        Err(errors) => report(&source, &props.filename, &errors, EXIT_COMPILE_ERROR),
     };
 
     // print tokens if flag is set
     if props.tokens.unwrap_or(false) {
-        println!("{}", begin("TOKENS"));
-        for token in tokens {
-            println!("> {:?}", token);
-        }
-        println!("{}", end("TOKENS"));
+        print_tokens(tokens);
     }
 
     // initialize parser with tokens
     let mut parser: Parser = Parser::new(tokens.to_vec());
 
-    // parse tokens
-    let statements: Vec<_> = parser.parse();
+    // parse tokens, refusing to go any further if the program has syntax errors
+    let statements: Vec<Stmt> = match parser.parse() {
+        Ok(statements) => statements,
+        Err(errors) => report(&source, &props.filename, &errors, EXIT_COMPILE_ERROR),
+    };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.policy = props.policy;
+
+    // resolve and optimize before anything is printed, so --ast shows the
+    // rewritten program
+    let globals = interpreter.global_names();
+    let statements: Vec<Stmt> = match resolve(statements, globals, props.opt.unwrap_or(false)) {
+        Ok(statements) => statements,
+        Err(errors) => report(&source, &props.filename, &errors, EXIT_COMPILE_ERROR),
+    };
 
     // print ast if flag is set
     if props.ast.unwrap_or(false) {
-        println!("{}", begin("AST"));
-        let mut printer: AstPrinter = AstPrinter::new();
-        printer.print(&statements);
-        println!("{}", end("AST"));
+        print_ast(&statements);
     }
 
     // print raw statements if flag is set
@@ -82,7 +151,27 @@ fn interpret(source: String, props: &Interpret) {
         println!("{}", end("STATEMENTS"));
     }
 
+    // compile to bytecode when it is going to be run or shown
+    if props.vm.unwrap_or(false) || props.disassemble.unwrap_or(false) {
+        let function = Compiler::compile(&statements);
+
+        // print bytecode if flag is set
+        if props.disassemble.unwrap_or(false) {
+            println!("{}", begin("BYTECODE"));
+            print!("{}", function.disassemble());
+            println!("{}", end("BYTECODE"));
+        }
+
+        if props.vm.unwrap_or(false) {
+            if let Err(error) = Vm::new(interpreter).run(function) {
+                report(&source, &props.filename, &[error], EXIT_RUNTIME_ERROR);
+            }
+            return;
+        }
+    }
+
     // interpret the statements
-    let mut interpreter = Interpreter::new();
-    interpreter.interpret(statements.clone());
+    if let Err(error) = interpreter.interpret(statements) {
+        report(&source, &props.filename, &[error], EXIT_RUNTIME_ERROR);
+    }
 }
\ No newline at end of file
@@ -33,98 +33,98 @@ struct PackagesLock {
 
 impl Command for Install {
     fn execute(&self) {
-        println!("> Installing package: {}", self.package);
-
-        let mut packages_lock = String::new();
-        let packages_lock_path: &str;
-
-        if !self.temp {
-            packages_lock_path = "rusticle.lock";
-        } else {
-            packages_lock_path = "rusticle.temp.lock";
+        if let Err(message) = install_package(&self.package, self.temp, &mut std::io::stdout()) {
+            eprintln!("> {}", message);
         }
+    }
+}
 
-        // Check if rusticle.lock exists, if not create it with an empty array
-        if let Ok(mut file) = fs::File::open(packages_lock_path) {
-            file.read_to_string(&mut packages_lock).expect("Unable to read rusticle.lock");
-        } else {
-            let mut file = fs::File::create(packages_lock_path).expect("Unable to create rusticle.lock");
-            file.write_all(b"{\"packages\": []}").expect("Unable to write to rusticle.lock");
-        }
+/// Downloads `package` into rusticle.lock, or rusticle.temp.lock when `temp`
+/// is set, writing progress to `output`. Failures are returned rather than
+/// printed so an interpreter can report them as its own errors.
+pub fn install_package(package: &str, temp: bool, output: &mut dyn Write) -> Result<(), String> {
+    let _ = writeln!(output, "> Installing package: {}", package);
+
+    let mut packages_lock = String::new();
+    let packages_lock_path: &str = if !temp {
+        "rusticle.lock"
+    } else {
+        "rusticle.temp.lock"
+    };
+
+    // Check if rusticle.lock exists, if not create it with an empty array
+    if let Ok(mut file) = fs::File::open(packages_lock_path) {
+        file.read_to_string(&mut packages_lock)
+            .map_err(|err| format!("Unable to read {}: {}", packages_lock_path, err))?;
+    } else {
+        let mut file = fs::File::create(packages_lock_path)
+            .map_err(|err| format!("Unable to create {}: {}", packages_lock_path, err))?;
+        file.write_all(b"{\"packages\": []}")
+            .map_err(|err| format!("Unable to write to {}: {}", packages_lock_path, err))?;
+    }
 
-        let mut packages_lock_data: PackagesLock = if !packages_lock.is_empty() {
-            serde_json::from_str(&packages_lock).expect("Unable to parse rusticle.lock")
-        } else {
-            PackagesLock { packages: Vec::new() }
-        };
+    let mut packages_lock_data: PackagesLock = if !packages_lock.is_empty() {
+        serde_json::from_str(&packages_lock)
+            .map_err(|err| format!("Unable to parse {}: {}", packages_lock_path, err))?
+    } else {
+        PackagesLock { packages: Vec::new() }
+    };
 
-        if packages_lock_data.packages.iter().any(|p: &Package| p.name == self.package) {
-            println!("> Package '{}' is already installed.", self.package);
-            return;
-        }
+    if packages_lock_data.packages.iter().any(|p: &Package| p.name == package) {
+        let _ = writeln!(output, "> Package '{}' is already installed.", package);
+        return Ok(());
+    }
 
-        let client: Client = Client::new();
-        let url: String = format!("http://127.0.0.1:8080/packages/{}", self.package);
-
-        match client.get(&url).send() {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<Package>() {
-                        Ok(package) => {
-                            let success: String = format!("> Package '{}' version '{}' installed successfully.", package.name, package.version);
-                            let parsed_functions: Vec<Function> = package.functions.into_iter().map(|f| {
-                                let params: Vec<String> = f.params.into_iter().map(|p| format!("{:?}", Token {
-                                    token_type: TokenType::Identifier,
-                                    lexeme: p,
-                                    line: 1,
-                                })).collect();
-
-                                let body: Vec<String> = f.body.into_iter().map(|stmt| {
-                                    let mut lexer = Lexer::new(stmt.clone());
-                                    let tokens = lexer.scan_tokens().expect("Failed to scan tokens");
-                                    let mut parser = Parser::new(tokens.clone());
-                                    format!("{:?}", parser.parse())
-                                }).collect();
-
-                                Function {
-                                    name: format!("{:?}", Token {
-                                        token_type: TokenType::Identifier,
-                                        lexeme: f.name,
-                                        line: 1,
-                                    }),
-                                    params,
-                                    body,
-                                }
-                            }).collect();
-
-                            let new_package = Package {
-                                name: package.name,
-                                version: package.version,
-                                functions: parsed_functions,
-                            };
-
-                            packages_lock_data.packages.push(new_package);
-                            let packages_lock_content: String = serde_json::to_string_pretty(&packages_lock_data).expect("Unable to serialize rusticle.lock");
-                            let mut file: fs::File = OpenOptions::new()
-                                .write(true)
-                                .create(true)
-                                .truncate(true)
-                                .open(packages_lock_path)
-                                .expect("Unable to open rusticle.lock");
-                            file.write_all(packages_lock_content.as_bytes()).expect("Unable to write to rusticle.lock");
-                            println!("{}", success);
-                        }
-                        Err(err) => {
-                            eprintln!("> Failed to parse package details or package doesn't exist: {}", err);
-                        }
-                    }
-                } else {
-                    eprintln!("> Failed to fetch package: {}", response.status());
-                }
-            }
-            Err(err) => {
-                eprintln!("> Failed to request package: {}", err);
-            }
-        }
+    let client: Client = Client::new();
+    let url: String = format!("http://127.0.0.1:8080/packages/{}", package);
+
+    let response = client
+        .get(&url)
+        .send()
+        .map_err(|err| format!("Failed to request package: {}", err))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch package: {}", response.status()));
     }
-}
\ No newline at end of file
+    let package: Package = response.json().map_err(|err| {
+        format!("Failed to parse package details or package doesn't exist: {}", err)
+    })?;
+
+    let success: String = format!("> Package '{}' version '{}' installed successfully.", package.name, package.version);
+    let parsed_functions: Vec<Function> = package.functions.into_iter().map(|f| {
+        let params: Vec<String> = f.params.into_iter().map(|p| format!("{:?}", Token::new(TokenType::Identifier, p, 1))).collect();
+
+        let body: Vec<String> = f.body.into_iter().map(|stmt| {
+            let mut lexer = Lexer::new(stmt);
+            let tokens = lexer.scan_tokens().map_err(|_| format!("Failed to scan package function '{}'.", f.name))?;
+            let mut parser = Parser::new(tokens.clone());
+            let statements = parser.parse().map_err(|_| format!("Failed to parse package function '{}'.", f.name))?;
+            Ok(format!("{:?}", statements))
+        }).collect::<Result<_, String>>()?;
+
+        Ok(Function {
+            name: format!("{:?}", Token::new(TokenType::Identifier, f.name, 1)),
+            params,
+            body,
+        })
+    }).collect::<Result<_, String>>()?;
+
+    let new_package = Package {
+        name: package.name,
+        version: package.version,
+        functions: parsed_functions,
+    };
+
+    packages_lock_data.packages.push(new_package);
+    let packages_lock_content: String = serde_json::to_string_pretty(&packages_lock_data)
+        .map_err(|err| format!("Unable to serialize {}: {}", packages_lock_path, err))?;
+    let mut file: fs::File = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(packages_lock_path)
+        .map_err(|err| format!("Unable to open {}: {}", packages_lock_path, err))?;
+    file.write_all(packages_lock_content.as_bytes())
+        .map_err(|err| format!("Unable to write to {}: {}", packages_lock_path, err))?;
+    let _ = writeln!(output, "{}", success);
+    Ok(())
+}
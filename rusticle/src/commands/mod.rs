@@ -1,6 +1,8 @@
 pub mod execute;
 pub mod reject;
 pub mod install;
+pub mod repl;
+pub mod test;
 
 pub trait Command {
     fn execute(&self);
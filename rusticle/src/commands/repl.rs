@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::commands::execute::{print_ast, print_tokens};
+use crate::commands::Command;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::policy::Policy;
+use crate::lexer::lexer::Lexer;
+use crate::parser::ast::{Object, Stmt};
+use crate::parser::parser::Parser;
+use crate::resolver::resolver::Resolver;
+use crate::utils::diagnostic::{render, Diagnostic};
+use crate::utils::token::{Token, TokenType};
+
+const PROMPT: &str = "lin> ";
+const CONTINUATION_PROMPT: &str = "...> ";
+
+/// Name errors are reported against, in place of a file name.
+const SOURCE_NAME: &str = "<repl>";
+
+const HELP: &str = "\
+:tokens <code>   show the tokens for <code> without running it
+:ast <code>      show the syntax tree for <code> without running it
+:env             list the variables defined so far
+:help            show this message
+:quit            leave the REPL (Ctrl-D works too)";
+
+/// Interactive session started when `rusticle` is run without a file.
+pub struct Repl {
+    pub policy: Policy,
+}
+
+impl Command for Repl {
+    fn execute(&self) {
+        let mut editor = match DefaultEditor::new() {
+            Ok(editor) => editor,
+            Err(e) => {
+                eprintln!("Unable to start the REPL: {}", e);
+                return;
+            }
+        };
+        let history = history_path();
+        if let Some(path) = &history {
+            // there is no history file before the first session
+            let _ = editor.load_history(path);
+        }
+
+        println!("Rusticle REPL. Type :help for commands, Ctrl-D to exit.");
+        let mut interpreter = Interpreter::new();
+        interpreter.policy = self.policy;
+        let mut session = Session { interpreter };
+        let mut buffer = String::new();
+        loop {
+            let prompt = if buffer.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    if buffer.is_empty() && line.trim_start().starts_with(':') {
+                        let _ = editor.add_history_entry(line.as_str());
+                        if !session.command(line.trim()) {
+                            break;
+                        }
+                        continue;
+                    }
+
+                    buffer.push_str(&line);
+                    buffer.push('\n');
+                    if is_incomplete(&buffer) {
+                        continue;
+                    }
+                    let _ = editor.add_history_entry(buffer.trim_end());
+                    session.run(&std::mem::take(&mut buffer));
+                }
+                // Ctrl-C abandons the input typed so far
+                Err(ReadlineError::Interrupted) => buffer.clear(),
+                Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    eprintln!("Unable to read input: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(path) = &history {
+            if let Err(e) = editor.save_history(path) {
+                eprintln!("Unable to save history to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+struct Session {
+    /// Kept for the whole session so every input sees earlier definitions.
+    interpreter: Interpreter,
+}
+
+impl Session {
+    /// Runs one complete input, echoing the value of a trailing bare
+    /// expression. Errors are reported and leave the session usable.
+    fn run(&mut self, source: &str) {
+        let Some(statements) = parse(source) else {
+            return;
+        };
+
+        if let Err(errors) = Resolver::with_globals(self.interpreter.global_names()).resolve(&statements) {
+            report(source, &errors);
+            return;
+        }
+
+        let echo = matches!(statements.last(), Some(Stmt::Expression { .. }));
+        match self.interpreter.interpret(statements) {
+            Ok(value) if echo && value != Object::Nil => println!("{}", value),
+            Ok(_) => {}
+            Err(error) => report(source, &[error]),
+        }
+    }
+
+    /// Handles a `:` command, returning false when the session should end.
+    fn command(&mut self, line: &str) -> bool {
+        let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+        match name {
+            ":tokens" => {
+                if let Some(tokens) = lex(argument) {
+                    print_tokens(&tokens);
+                }
+            }
+            ":ast" => {
+                if let Some(statements) = parse(argument) {
+                    print_ast(&statements);
+                }
+            }
+            ":env" => self.print_env(),
+            ":help" => println!("{}", HELP),
+            ":quit" | ":exit" => return false,
+            _ => eprintln!("Unknown command '{}'. Type :help for a list.", name),
+        }
+        true
+    }
+
+    /// Lists the globals defined in this session, leaving out the
+    /// built-in functions every session starts with.
+    fn print_env(&self) {
+        let globals = self.interpreter.globals.borrow();
+        let mut variables: Vec<(&String, &Object)> = globals
+            .entries()
+            .filter(|(_, value)| !matches!(value, Object::Native(_)))
+            .collect();
+        if variables.is_empty() {
+            println!("(no variables defined)");
+            return;
+        }
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in variables {
+            println!("{} = {}", name, value);
+        }
+    }
+}
+
+fn lex(source: &str) -> Option<Vec<Token>> {
+    match Lexer::new(source.to_string()).scan_tokens() {
+        Ok(tokens) => Some(tokens.clone()),
+        Err(errors) => {
+            report(source, &errors);
+            None
+        }
+    }
+}
+
+fn parse(source: &str) -> Option<Vec<Stmt>> {
+    let mut tokens = lex(source)?;
+    Parser::terminate(&mut tokens);
+
+    match Parser::new(tokens).parse() {
+        Ok(statements) => Some(statements),
+        Err(errors) => {
+            report(source, &errors);
+            None
+        }
+    }
+}
+
+/// Whether the input stops inside a string or before every bracket it
+/// opened is closed, so the next line should continue it.
+fn is_incomplete(source: &str) -> bool {
+    let mut lexer = Lexer::new(source.to_string());
+    let tokens = match lexer.scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            return errors
+                .iter()
+                .any(|error| error.message.starts_with("Unterminated string"));
+        }
+    };
+
+    let mut depth: isize = 0;
+    for token in tokens {
+        match token.token_type {
+            TokenType::LeftParen
+            | TokenType::LeftBrace
+            | TokenType::LeftBracket
+            | TokenType::HashBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+fn report(source: &str, errors: &[impl Diagnostic]) {
+    for error in errors {
+        eprintln!("{}\n", render(source, SOURCE_NAME, error));
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".rusticle_history"))
+}
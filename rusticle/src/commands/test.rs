@@ -0,0 +1,195 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+use crate::commands::Command;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::policy::Policy;
+use crate::lexer::lexer::Lexer;
+use crate::parser::ast::Stmt;
+use crate::parser::parser::Parser;
+use crate::resolver::resolver::Resolver;
+use crate::utils::diagnostic::{render, Diagnostic};
+use crate::utils::token::Token;
+
+/// Exit status when a test fails or a test file can't be run.
+pub const EXIT_TEST_FAILURE: i32 = 1;
+
+/// Runs the `test` blocks of every `.lin` file under `paths`, each in a
+/// fresh interpreter.
+pub struct Test {
+    /// Files and directories to search; the current directory when empty.
+    pub paths: Vec<String>,
+    /// Only tests whose name contains this are run.
+    pub filter: Option<String>,
+    pub policy: Policy,
+}
+
+#[derive(Default)]
+struct Summary {
+    passed: usize,
+    failed: usize,
+    filtered_out: usize,
+    /// A heading and report for every failed test or unusable file.
+    failures: Vec<(String, String)>,
+}
+
+impl Command for Test {
+    fn execute(&self) {
+        let roots: Vec<String> = if self.paths.is_empty() {
+            vec![".".to_string()]
+        } else {
+            self.paths.clone()
+        };
+
+        let mut summary = Summary::default();
+        let mut files = Vec::new();
+        for root in &roots {
+            if let Err(e) = discover(Path::new(root), &mut files) {
+                summary.failed += 1;
+                summary.failures.push((root.clone(), format!("Unable to read {}: {}", root, e)));
+            }
+        }
+        for file in &files {
+            self.run_file(file, &mut summary);
+        }
+
+        if !summary.failures.is_empty() {
+            println!("\nfailures:");
+            for (heading, report) in &summary.failures {
+                println!("\n---- {} ----\n{}", heading, report);
+            }
+        }
+        println!(
+            "\ntest result: {}. {} passed; {} failed; {} filtered out",
+            if summary.failed == 0 { "ok" } else { "FAILED" },
+            summary.passed,
+            summary.failed,
+            summary.filtered_out
+        );
+        if summary.failed > 0 {
+            process::exit(EXIT_TEST_FAILURE);
+        }
+    }
+}
+
+impl Test {
+    fn run_file(&self, file: &Path, summary: &mut Summary) {
+        let path = file.display().to_string();
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                summary.failed += 1;
+                summary.failures.push((path.clone(), format!("Unable to read {}: {}", path, e)));
+                return;
+            }
+        };
+        let statements = match compile(&source, &path) {
+            Ok(statements) => statements,
+            Err(report) => {
+                summary.failed += 1;
+                summary.failures.push((path, report));
+                return;
+            }
+        };
+
+        // everything outside the test blocks is set-up shared by every test
+        let (tests, setup): (Vec<Stmt>, Vec<Stmt>) = statements
+            .into_iter()
+            .partition(|stmt| matches!(stmt, Stmt::Test { .. }));
+        let tests: Vec<(Token, Vec<Stmt>)> = tests
+            .into_iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Test { name, body } => Some((name, body)),
+                _ => None,
+            })
+            .filter(|(name, _)| {
+                let selected = self.filter.as_ref().is_none_or(|filter| name.lexeme.contains(filter.as_str()));
+                if !selected {
+                    summary.filtered_out += 1;
+                }
+                selected
+            })
+            .collect();
+        if tests.is_empty() {
+            return;
+        }
+
+        println!("\nrunning {} test{} in {}", tests.len(), if tests.len() == 1 { "" } else { "s" }, path);
+        for (name, body) in tests {
+            let mut program = setup.clone();
+            program.push(Stmt::Block { statements: body });
+
+            // output is only shown for tests that fail
+            let output = Rc::new(RefCell::new(Vec::new()));
+            let mut interpreter = Interpreter::new();
+            interpreter.output = output.clone();
+            interpreter.policy = self.policy;
+
+            match interpreter.interpret(program) {
+                Ok(_) => {
+                    println!("test {} ... ok", name.lexeme);
+                    summary.passed += 1;
+                }
+                Err(error) => {
+                    println!("test {} ... FAILED", name.lexeme);
+                    summary.failed += 1;
+                    let printed = String::from_utf8_lossy(&output.borrow()).into_owned();
+                    let report = format!("{}{}", printed, render(&source, &path, &error));
+                    summary.failures.push((format!("{}: {}", path, name.lexeme), report));
+                }
+            }
+        }
+    }
+}
+
+/// Lexes, parses and resolves a test file, rendering every error on failure.
+fn compile(source: &str, path: &str) -> Result<Vec<Stmt>, String> {
+    let tokens = Lexer::new(source.to_string())
+        .scan_tokens()
+        .map_err(|errors| report(source, path, &errors))?
+        .clone();
+    let statements = Parser::new(tokens)
+        .parse()
+        .map_err(|errors| report(source, path, &errors))?;
+    // every test starts from a fresh interpreter, so its built-ins are the
+    // only globals defined up front
+    Resolver::with_globals(Interpreter::new().global_names())
+        .resolve(&statements)
+        .map_err(|errors| report(source, path, &errors))?;
+    Ok(statements)
+}
+
+fn report(source: &str, path: &str, errors: &[impl Diagnostic]) -> String {
+    let rendered: Vec<String> = errors.iter().map(|error| render(source, path, error)).collect();
+    rendered.join("\n\n")
+}
+
+/// Collects `path` if it is a file, or every `.lin` file below it in
+/// name order if it is a directory. Hidden directories and `target` are
+/// skipped.
+fn discover(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        if entry.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                discover(&entry, files)?;
+            }
+        } else if entry.extension().is_some_and(|extension| extension == "lin") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::error;
+use std::fmt;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::policy::Policy;
+use crate::lexer::lexer::Lexer;
+use crate::parser::ast::{Native, Object, Stmt};
+use crate::parser::parser::Parser;
+use crate::resolver::resolver::Resolver;
+use crate::utils::diagnostic::Diagnostic;
+use crate::utils::error::{LexError, ParseError, ResolveError, RuntimeError};
+
+/// Why a script given to an [`Engine`] didn't run to completion.
+#[derive(Debug)]
+pub enum Error {
+    /// The script file couldn't be read.
+    Io { path: PathBuf, error: io::Error },
+    Lex(Vec<LexError>),
+    Parse(Vec<ParseError>),
+    Resolve(Vec<ResolveError>),
+    Runtime(RuntimeError),
+}
+
+impl Error {
+    /// The errors that point into the source, for use with
+    /// [`render`](crate::utils::diagnostic::render). Empty for `Io`.
+    pub fn diagnostics(&self) -> Vec<&dyn Diagnostic> {
+        match self {
+            Error::Io { .. } => Vec::new(),
+            Error::Lex(errors) => errors.iter().map(|e| e as &dyn Diagnostic).collect(),
+            Error::Parse(errors) => errors.iter().map(|e| e as &dyn Diagnostic).collect(),
+            Error::Resolve(errors) => errors.iter().map(|e| e as &dyn Diagnostic).collect(),
+            Error::Runtime(error) => vec![error],
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Error::Io { path, error } = self {
+            return write!(f, "Error reading {}: {}", path.display(), error);
+        }
+        let lines: Vec<String> = self.diagnostics().iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Deepest an [`Engine`]'s scripts may nest calls unless
+/// [`set_max_call_depth`](Engine::set_max_call_depth) says otherwise. Each
+/// call takes native stack, and this many fit in the 2 MiB Rust gives a
+/// spawned thread, even in a debug build, so a runaway recursion fails with
+/// "Stack overflow." instead of aborting the host.
+pub const DEFAULT_CALL_DEPTH: usize = 200;
+
+/// An embedded Lin interpreter whose globals persist between runs.
+///
+/// Scripts start out sandboxed: they write to standard output, read from
+/// standard input and may not touch files or the network until a
+/// [`Policy`] allowing it is set.
+pub struct Engine {
+    interpreter: Interpreter,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        let mut interpreter = Interpreter::new();
+        interpreter.max_call_depth = DEFAULT_CALL_DEPTH;
+        Engine { interpreter }
+    }
+
+    /// Runs `source`, returning the value of its last statement when that is
+    /// a bare expression and `nil` otherwise. The `;` after a final
+    /// expression can be left out, so `eval("1 + 2")` gives `3`.
+    pub fn eval(&mut self, source: &str) -> Result<Object, Error> {
+        let statements = self.compile(source)?;
+        self.interpreter.interpret(statements).map_err(Error::Runtime)
+    }
+
+    /// Reads and runs a script file, as [`eval`](Engine::eval) does.
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<Object, Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| Error::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        self.eval(&source)
+    }
+
+    /// Sends everything scripts print to `output`. Keep a clone of the `Rc`
+    /// to read it back, for example with an `Rc<RefCell<Vec<u8>>>`.
+    pub fn set_output(&mut self, output: Rc<RefCell<dyn Write>>) {
+        self.interpreter.output = output;
+    }
+
+    /// Makes `input()` read lines from `input`.
+    pub fn set_input(&mut self, input: Rc<RefCell<dyn BufRead>>) {
+        self.interpreter.input = input;
+    }
+
+    /// Lets scripts nest calls `depth` deep. Only raise it for an engine
+    /// used on a thread with a bigger stack: one with
+    /// [`STACK_SIZE`](crate::interpreter::interpreter::STACK_SIZE) bytes fits
+    /// [`MAX_CALL_DEPTH`](crate::interpreter::interpreter::MAX_CALL_DEPTH),
+    /// the limit the `rusticle` binary uses.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.interpreter.max_call_depth = depth;
+    }
+
+    /// Sets what scripts are allowed to do besides input and output.
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.interpreter.policy = policy;
+    }
+
+    /// Defines or replaces a global variable.
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.interpreter.globals.borrow_mut().define(name.to_string(), value);
+    }
+
+    /// The current value of a global variable, if it is defined.
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.interpreter.globals.borrow().get_at(0, name)
+    }
+
+    /// Makes a Rust closure callable from Lin as `name(...)`. It receives
+    /// every argument the script passes, and an `Err` message becomes a
+    /// runtime error at the call.
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        function: impl Fn(&[Object]) -> Result<Object, String> + 'static,
+    ) {
+        let native = Native::new(name, None, move |_, args| function(args));
+        self.set_global(name, Object::Native(native.into()));
+    }
+
+    fn compile(&self, source: &str) -> Result<Vec<Stmt>, Error> {
+        let mut tokens = Lexer::new(source.to_string())
+            .scan_tokens()
+            .map_err(Error::Lex)?
+            .clone();
+        Parser::terminate(&mut tokens);
+        let statements = Parser::new(tokens).parse().map_err(Error::Parse)?;
+
+        Resolver::with_globals(self.interpreter.global_names())
+            .resolve(&statements)
+            .map_err(Error::Resolve)?;
+        Ok(statements)
+    }
+}
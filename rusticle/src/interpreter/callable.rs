@@ -0,0 +1,15 @@
+use crate::interpreter::interpreter::{Interpreter, Outcome};
+use crate::parser::ast::Object;
+use crate::utils::token::Token;
+
+/// Anything a Lin program can call: functions written in Lin, natives
+/// written in Rust, and classes, which build an instance.
+pub trait Callable {
+    /// Number of arguments expected, or `None` when any count is accepted
+    /// and the callee checks them itself.
+    fn arity(&self) -> Option<usize>;
+
+    /// Runs the call. `paren` is the call's closing parenthesis, which
+    /// errors raised by the callee itself are reported at.
+    fn call(&self, interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Outcome;
+}
@@ -1,32 +1,80 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use crate::{parser::ast::Object, utils::token::Token};
 
 #[derive(Clone)]
 pub struct Environment {
     values: HashMap<String, Object>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
 }
 
 impl Environment {
     pub fn new() -> Self {
         Environment {
             values: HashMap::new(),
+            enclosing: None,
+        }
+    }
+
+    /// Creates a scope nested inside `enclosing`; lookups fall back to it.
+    pub fn new_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
         }
     }
 
+    /// Variables defined directly in this scope, in no particular order.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Object)> {
+        self.values.iter()
+    }
+
     pub fn define(&mut self, name: String, value: Object) {
         self.values.insert(name, value);
     }
 
     pub fn get(&self, name: &Token) -> Option<Object> {
-        self.values.get(&name.lexeme).cloned()
+        match self.values.get(&name.lexeme) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Reads `name` from the scope exactly `distance` levels up the chain.
+    pub fn get_at(&self, distance: usize, name: &str) -> Option<Object> {
+        if distance == 0 {
+            self.values.get(name).cloned()
+        } else {
+            self.enclosing.as_ref()?.borrow().get_at(distance - 1, name)
+        }
+    }
+
+    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Object) -> Result<(), String> {
+        if distance == 0 {
+            self.values.insert(name.lexeme.clone(), value);
+            Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign_at(distance - 1, name, value)
+        } else {
+            Err(format!("Undefined variable '{}'.", name.lexeme))
+        }
     }
 
     pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), String> {
         if self.values.contains_key(&name.lexeme) {
             self.values.insert(name.lexeme.clone(), value);
             Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)
         } else {
             Err(format!("Undefined variable '{}'.", name.lexeme))
         }
     }
-}
\ No newline at end of file
+}
@@ -1,12 +1,65 @@
-use crate::commands::Command;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
 use crate::interpreter::environment::Environment;
+use crate::interpreter::natives;
+use crate::interpreter::operations;
+use crate::interpreter::policy::{Capability, Policy};
 use crate::interpreter::visitor::Visitor;
-use crate::parser::ast::{Expr, Function, Object, Stmt};
-use crate::utils::token::TokenType;
-use crate::commands::install::Install;
+use crate::parser::ast::{Class, Expr, Function, Instance, Object, Stmt};
+use crate::resolver::resolver::Resolver;
+use crate::utils::error::RuntimeError;
+use crate::utils::token::{Token, TokenType};
+use crate::commands::install::install_package;
+
+/// Non-local exits that unwind out of nested statements.
+pub enum Unwind {
+    /// A `dede` statement carrying the value back to the enclosing call.
+    Return { keyword: Token, value: Object },
+    /// A `ruk` statement leaving the innermost loop.
+    Break,
+    /// An `agla` statement skipping to the innermost loop's next iteration.
+    Continue,
+    /// A runtime error on its way up to `Interpreter::interpret`.
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+pub type Outcome = Result<Object, Unwind>;
+
+/// Deepest nesting of calls to Lin functions before a program is stopped
+/// with "Stack overflow.", unless `max_call_depth` is changed. The bytecode
+/// VM stops at the same depth.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Native stack a thread needs for the tree-walker to reach
+/// `MAX_CALL_DEPTH` without overflowing first, even in a debug build.
+pub const STACK_SIZE: usize = 1 << 30;
 
 pub struct Interpreter {
-    environment: Environment,
+    /// The outermost scope; function bodies are nested directly inside it.
+    pub globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    /// Where `likh` and prompts go; standard output unless replaced.
+    pub output: Rc<RefCell<dyn Write>>,
+    /// Where `input()` reads from; standard input unless replaced.
+    pub input: Rc<RefCell<dyn BufRead>>,
+    /// What the program may do besides reading input and writing output.
+    pub policy: Policy,
+    /// Deepest the calls may nest, which has to fit in the native stack of
+    /// the thread running the program.
+    pub max_call_depth: usize,
+    /// Number of Lin function calls currently running.
+    calls: usize,
 }
 
 #[derive(serde::Deserialize)]
@@ -18,116 +71,236 @@ struct PackagesLock {
 struct Package {
     name: String,
     version: String,
-    functions: Vec<Function>,
+    functions: Vec<Rc<Function>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        natives::define(&mut globals.borrow_mut());
         Interpreter {
-            environment: Environment::new(),
+            globals: globals.clone(),
+            environment: globals,
+            output: Rc::new(RefCell::new(io::stdout())),
+            input: Rc::new(RefCell::new(io::BufReader::new(io::stdin()))),
+            policy: Policy::default(),
+            max_call_depth: MAX_CALL_DEPTH,
+            calls: 0,
         }
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+    /// Names defined in the global scope, built-in functions included, for
+    /// a resolver checking code that will run here.
+    pub fn global_names(&self) -> Vec<String> {
+        self.globals.borrow().entries().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Runs a program, returning the value of its last statement when that
+    /// is a bare expression and `nil` otherwise.
+    pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<Object, RuntimeError> {
+        let mut value = Object::Nil;
         for statement in statements {
-            self.execute(&statement);
+            match self.execute(&statement) {
+                Ok(result) if matches!(statement, Stmt::Expression { .. }) => value = result,
+                Ok(_) => value = Object::Nil,
+                Err(Unwind::Return { keyword, .. }) => {
+                    return Err(RuntimeError::new(&keyword, "Can't return from top-level code."));
+                }
+                Err(Unwind::Error(error)) => return Err(error),
+                Err(Unwind::Break | Unwind::Continue) => {
+                    unreachable!("the parser rejects 'ruk' and 'agla' outside of a loop")
+                }
+            }
         }
+        Ok(value)
     }
 
-    fn execute(&mut self, stmt: &Stmt) {
-        stmt.accept(self);
+    fn execute(&mut self, stmt: &Stmt) -> Outcome {
+        stmt.accept(self)
     }
 
-    fn evaluate(&mut self, expr: &Expr) -> Object {
+    fn evaluate(&mut self, expr: &Expr) -> Outcome {
         expr.accept(self)
     }
 
-    pub fn execute_block(&mut self, statements: &[Stmt], environment: Environment) -> Object {
-        let previous = self.environment.clone();
-        self.environment = environment;
+    fn look_up_variable(&self, name: &Token, depth: Option<usize>) -> Result<Object, RuntimeError> {
+        let value = match depth {
+            Some(distance) => self.environment.borrow().get_at(distance, &name.lexeme),
+            None => self.globals.borrow().get(name),
+        };
+        value.ok_or_else(|| {
+            RuntimeError::new(name, &format!("Undefined variable '{}'.", name.lexeme))
+        })
+    }
+
+    /// Makes a packaged function available as a global. Its body was never
+    /// resolved with the importing program, so that happens here.
+    fn load_function(
+        &mut self,
+        keyword: &Token,
+        function: &Rc<Function>,
+        package_name: &str,
+    ) -> Result<(), RuntimeError> {
+        if let Err(errors) = Resolver::new().resolve_packaged(function) {
+            let details: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+            let message = format!(
+                "Function '{}' in package '{}' is invalid:\n{}",
+                function.name.lexeme,
+                package_name,
+                details.join("\n")
+            );
+            return Err(RuntimeError::new(keyword, &message));
+        }
+        self.globals
+            .borrow_mut()
+            .define(function.name.lexeme.clone(), Object::Function(function.clone()));
+        Ok(())
+    }
 
-        for statement in statements {
-            self.execute(statement);
+    /// Runs a function body, failing at `paren` instead when calls are
+    /// already nested `max_call_depth` deep.
+    pub fn execute_call(&mut self, paren: &Token, body: &[Stmt], environment: Environment) -> Outcome {
+        if self.calls >= self.max_call_depth {
+            return Err(RuntimeError::new(paren, "Stack overflow.").into());
         }
+        self.calls += 1;
+        let result = self.execute_block(body, environment);
+        self.calls -= 1;
+        result
+    }
+
+    pub fn execute_block(&mut self, statements: &[Stmt], environment: Environment) -> Outcome {
+        let previous = std::mem::replace(
+            &mut self.environment,
+            Rc::new(RefCell::new(environment)),
+        );
+
+        // restore the outer environment even when a `dede` unwinds through us
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement).map(|_| ()));
 
         self.environment = previous;
-        Object::Nil
+        result.map(|_| Object::Nil)
     }
 }
 
 #[allow(unused_variables)]
-impl Visitor<Object> for Interpreter {
-    fn visit_assign_expr(&mut self, expr: &Expr) -> Object {
-        if let Expr::Assign { name, value } = expr {
-            let value = self.evaluate(value);
-            self.environment.assign(name, value.clone()).unwrap();
-            value
+impl Visitor<Outcome> for Interpreter {
+    fn visit_assign_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Assign { name, value, depth } = expr {
+            let value = self.evaluate(value)?;
+            let assigned = match depth.get() {
+                Some(distance) => self
+                    .environment
+                    .borrow_mut()
+                    .assign_at(distance, name, value.clone()),
+                None => self.globals.borrow_mut().assign(name, value.clone()),
+            };
+            assigned.map_err(|message| RuntimeError::new(name, &message))?;
+            Ok(value)
         } else {
             panic!("Expected assign expression");
         }
     }
 
-    fn visit_binary_expr(&mut self, expr: &Expr) -> Object {
+    fn visit_binary_expr(&mut self, expr: &Expr) -> Outcome {
         if let Expr::Binary {
             left,
             operator,
             right,
         } = expr
         {
-            let left = self.evaluate(left);
-            let right = self.evaluate(right);
-
-            match operator.token_type {
-                TokenType::Plus => Object::Number(left.as_number() + right.as_number()),
-                TokenType::Minus => Object::Number(left.as_number() - right.as_number()),
-                TokenType::Star => Object::Number(left.as_number() * right.as_number()),
-                TokenType::Slash => Object::Number(left.as_number() / right.as_number()),
-                TokenType::Greater => Object::Bool(left.as_number() > right.as_number()),
-                TokenType::GreaterEqual => Object::Bool(left.as_number() >= right.as_number()),
-                TokenType::Less => Object::Bool(left.as_number() < right.as_number()),
-                TokenType::LessEqual => Object::Bool(left.as_number() <= right.as_number()),
-                TokenType::BangEqual => Object::Bool(left != right),
-                TokenType::EqualEqual => Object::Bool(left == right),
-                _ => panic!("Unexpected binary operator"),
-            }
+            let left = self.evaluate(left)?;
+            let right = self.evaluate(right)?;
+            Ok(operations::binary(operator, &left, &right)?)
         } else {
             panic!("Expected binary expression");
         }
     }
 
-    fn visit_call_expr(&mut self, expr: &Expr) -> Object {
+    fn visit_call_expr(&mut self, expr: &Expr) -> Outcome {
         if let Expr::Call {
             callee,
             paren,
             arguments,
         } = expr
         {
-            let callee = self.evaluate(callee);
+            let callee = self.evaluate(callee)?;
 
             let mut args = Vec::new();
             for arg in arguments {
-                args.push(self.evaluate(arg));
+                args.push(self.evaluate(arg)?);
             }
 
-            match callee {
-                Object::Function(func) => {
-                    if args.len() != func.arity() {
-                        panic!(
-                            "Expected {} arguments but got {}.",
-                            func.arity(),
-                            args.len()
-                        );
-                    }
-                    func.call(self, args)
-                }
-                _ => panic!("Can only call functions and classes."),
+            let Some(callable) = callee.as_callable() else {
+                return Err(RuntimeError::new(paren, "Can only call functions and classes.").into());
+            };
+            if let Some(arity) = callable.arity() {
+                operations::check_arity(paren, arity, args.len())?;
             }
+            callable.call(self, paren, args)
         } else {
             panic!("Expected call expression")
         }
     }
 
-    fn visit_grouping_expr(&mut self, expr: &Expr) -> Object {
+    fn visit_compound_set_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::CompoundSet { object, name, operator, value } = expr {
+            let Object::Instance(instance) = self.evaluate(object)? else {
+                return Err(RuntimeError::new(name, "Only instances have properties.").into());
+            };
+            let Some(current) = Instance::get(&instance, name, self) else {
+                let message = format!("Undefined property '{}'.", name.lexeme);
+                return Err(RuntimeError::new(name, &message).into());
+            };
+            let value = self.evaluate(value)?;
+            let value = operations::binary(operator, &current, &value)?;
+            instance.borrow_mut().set(name, value.clone());
+            Ok(value)
+        } else {
+            panic!("Expected compound set expression")
+        }
+    }
+
+    fn visit_compound_index_set_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::CompoundIndexSet { object, bracket, index, operator, value } = expr {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+            let current = operations::index(bracket, &object, &index)?;
+            let value = self.evaluate(value)?;
+            let value = operations::binary(operator, &current, &value)?;
+            operations::index_set(bracket, &object, &index, value.clone())?;
+            Ok(value)
+        } else {
+            panic!("Expected compound index set expression")
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Get { object, name } = expr {
+            match self.evaluate(object)? {
+                Object::Instance(instance) => match Instance::get(&instance, name, self) {
+                    Some(value) => Ok(value),
+                    None => {
+                        let message = format!("Undefined property '{}'.", name.lexeme);
+                        Err(RuntimeError::new(name, &message).into())
+                    }
+                },
+                _ => Err(RuntimeError::new(name, "Only instances have properties.").into()),
+            }
+        } else {
+            panic!("Expected get expression")
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Outcome {
         if let Expr::Grouping { expression } = expr {
             self.evaluate(expression)
         } else {
@@ -135,31 +308,89 @@ impl Visitor<Object> for Interpreter {
         }
     }
 
-    fn visit_literal_expr(&mut self, expr: &Expr) -> Object {
+    fn visit_index_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Index { object, bracket, index } = expr {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+            Ok(operations::index(bracket, &object, &index)?)
+        } else {
+            panic!("Expected index expression")
+        }
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::IndexSet { object, bracket, index, value } = expr {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+            let value = self.evaluate(value)?;
+            operations::index_set(bracket, &object, &index, value.clone())?;
+            Ok(value)
+        } else {
+            panic!("Expected index set expression")
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Interpolation { parts } = expr {
+            let mut text = String::new();
+            for part in parts {
+                text.push_str(&self.evaluate(part)?.to_string());
+            }
+            Ok(Object::String(text))
+        } else {
+            panic!("Expected interpolation expression")
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::List { elements } = expr {
+            let mut values = Vec::new();
+            for element in elements {
+                values.push(self.evaluate(element)?);
+            }
+            Ok(Object::List(Rc::new(RefCell::new(values))))
+        } else {
+            panic!("Expected list expression")
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Map { brace, entries } = expr {
+            let mut map = IndexMap::new();
+            for (key, value) in entries {
+                let key = self.evaluate(key)?;
+                let key = operations::map_key(brace, &key)?;
+                map.insert(key, self.evaluate(value)?);
+            }
+            Ok(Object::Map(Rc::new(RefCell::new(map))))
+        } else {
+            panic!("Expected map expression")
+        }
+    }
+
+    fn visit_literal_expr(&mut self, expr: &Expr) -> Outcome {
         if let Expr::Literal { value } = expr {
-            value.clone()
+            Ok(value.clone())
         } else {
             panic!("Expected literal expression")
         }
     }
 
-    fn visit_logical_expr(&mut self, expr: &Expr) -> Object {
+    fn visit_logical_expr(&mut self, expr: &Expr) -> Outcome {
         if let Expr::Logical {
             left,
             operator,
             right,
         } = expr
         {
-            let left = self.evaluate(left);
+            let left = self.evaluate(left)?;
 
             if operator.token_type == TokenType::Or {
                 if left.as_bool() {
-                    return left;
-                }
-            } else {
-                if !left.as_bool() {
-                    return left;
+                    return Ok(left);
                 }
+            } else if !left.as_bool() {
+                return Ok(left);
             }
 
             self.evaluate(right)
@@ -168,202 +399,352 @@ impl Visitor<Object> for Interpreter {
         }
     }
 
-    fn visit_unary_expr(&mut self, expr: &Expr) -> Object {
-        if let Expr::Unary { operator, right } = expr {
-            let right = self.evaluate(right);
+    fn visit_set_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Set { object, name, value } = expr {
+            let Object::Instance(instance) = self.evaluate(object)? else {
+                return Err(RuntimeError::new(name, "Only instances have fields.").into());
+            };
+            let value = self.evaluate(value)?;
+            instance.borrow_mut().set(name, value.clone());
+            Ok(value)
+        } else {
+            panic!("Expected set expression")
+        }
+    }
+
+    fn visit_slice_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Slice { object, bracket, start, end } = expr {
+            let object = self.evaluate(object)?;
+            let start = match start {
+                Some(start) => Some(self.evaluate(start)?),
+                None => None,
+            };
+            let end = match end {
+                Some(end) => Some(self.evaluate(end)?),
+                None => None,
+            };
+
+            Ok(operations::slice(bracket, &object, start.as_ref(), end.as_ref())?)
+        } else {
+            panic!("Expected slice expression")
+        }
+    }
 
-            match operator.token_type {
-                TokenType::Minus => Object::Number(-right.as_number()),
-                TokenType::Bang => Object::Bool(!right.as_bool()),
-                _ => panic!("Unexpected unary operator"),
+    fn visit_super_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Super { method, depth, .. } = expr {
+            let distance = depth.get().expect("'super' is always resolved to a local scope");
+            let environment = self.environment.borrow();
+            let Some(Object::Class(superclass)) = environment.get_at(distance, "super") else {
+                panic!("Expected 'super' to be a class");
+            };
+            // `this` lives in the scope just inside the one holding `super`
+            let instance = environment.get_at(distance - 1, "this").unwrap();
+            drop(environment);
+
+            match superclass.find_method(&method.lexeme) {
+                Some(found) => Ok(Object::Function(Rc::new(found.bind(instance, self)))),
+                None => {
+                    let message = format!("Undefined property '{}'.", method.lexeme);
+                    Err(RuntimeError::new(method, &message).into())
+                }
             }
+        } else {
+            panic!("Expected super expression")
+        }
+    }
+
+    fn visit_this_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::This { keyword, depth } = expr {
+            Ok(self.look_up_variable(keyword, depth.get())?)
+        } else {
+            panic!("Expected this expression")
+        }
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Unary { operator, right } = expr {
+            let right = self.evaluate(right)?;
+            Ok(operations::unary(operator, &right)?)
         } else {
             panic!("Expected unary expression")
         }
     }
 
-    fn visit_variable_expr(&mut self, expr: &Expr) -> Object {
-        if let Expr::Variable { name } = expr {
-            self.environment.get(name).unwrap()
+    fn visit_variable_expr(&mut self, expr: &Expr) -> Outcome {
+        if let Expr::Variable { name, depth } = expr {
+            Ok(self.look_up_variable(name, depth.get())?)
         } else {
             panic!("Expected variable expression")
         }
     }
 
-    fn visit_block_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_block_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::Block { statements } = stmt {
-            for statement in statements {
-                self.execute(statement);
-            }
+            let environment = Environment::new_enclosing(self.environment.clone());
+            self.execute_block(statements, environment)?;
         }
-        Object::Nil
+        Ok(Object::Nil)
     }
 
-    fn visit_class_stmt(&mut self, stmt: &Stmt) -> Object {
-        // Implement class statement logic
-        Object::Nil
+    fn visit_class_stmt(&mut self, stmt: &Stmt) -> Outcome {
+        if let Stmt::Class { name, superclass, methods } = stmt {
+            let superclass = match superclass {
+                Some(superclass_expr) => match self.evaluate(superclass_expr)? {
+                    Object::Class(class) => Some(class),
+                    _ => {
+                        let token = match superclass_expr {
+                            Expr::Variable { name, .. } => name,
+                            _ => name,
+                        };
+                        return Err(RuntimeError::new(token, "Superclass must be a class.").into());
+                    }
+                },
+                None => None,
+            };
+
+            // methods of a subclass close over a scope holding `super`
+            let enclosing = self.environment.clone();
+            if let Some(superclass) = &superclass {
+                let mut environment = Environment::new_enclosing(enclosing.clone());
+                environment.define("super".to_string(), Object::Class(superclass.clone()));
+                self.environment = Rc::new(RefCell::new(environment));
+            }
+
+            let mut class_methods = HashMap::new();
+            for method in methods {
+                if let Stmt::Function { name, params, body } = method {
+                    let function = Function::new(
+                        name.clone(),
+                        params.clone(),
+                        Rc::new(body.clone()),
+                        self.environment.clone(),
+                        name.lexeme == "init",
+                    );
+                    class_methods.insert(name.lexeme.clone(), Rc::new(function));
+                }
+            }
+
+            self.environment = enclosing;
+
+            let class = Class::new(name.lexeme.clone(), superclass, class_methods);
+            self.environment
+                .borrow_mut()
+                .define(name.lexeme.clone(), Object::Class(Rc::new(class)));
+        }
+        Ok(Object::Nil)
     }
 
-    fn visit_expression_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::Expression { expression } = stmt {
-            self.evaluate(expression);
+            self.evaluate(expression)
+        } else {
+            panic!("Expected expression statement")
         }
-        Object::Nil
     }
 
-    fn visit_function_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_function_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::Function { name, params, body } = stmt {
-            let function =
-                Object::Function(Function::new(name.clone(), params.clone(), body.clone()));
-            self.environment.define(name.lexeme.clone(), function);
+            let function = Object::Function(Rc::new(Function::new(
+                name.clone(),
+                params.clone(),
+                Rc::new(body.clone()),
+                self.environment.clone(),
+                false,
+            )));
+            self.environment.borrow_mut().define(name.lexeme.clone(), function);
         }
-        Object::Nil
+        Ok(Object::Nil)
     }
 
-    fn visit_if_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_if_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::If {
             condition,
             then_branch,
             else_branch,
         } = stmt
         {
-            if self.evaluate(condition).as_bool() {
-                self.execute(then_branch);
+            if self.evaluate(condition)?.as_bool() {
+                self.execute(then_branch)?;
             } else if let Some(else_branch) = else_branch {
-                self.execute(else_branch);
+                self.execute(else_branch)?;
             }
         }
-        Object::Nil
+        Ok(Object::Nil)
     }
 
-    fn visit_print_stmt(&mut self, stmt: &Stmt) -> Object {
-        if let Stmt::Print { expression } = stmt {
-            let value: Object = self.evaluate(expression);
-            println!("{:?}", value);
+    fn visit_print_stmt(&mut self, stmt: &Stmt) -> Outcome {
+        if let Stmt::Print { expressions } = stmt {
+            let mut values = Vec::new();
+            for expression in expressions {
+                values.push(self.evaluate(expression)?.to_string());
+            }
+            // like `println!` into a closed pipe, output to a failed sink is lost
+            let _ = writeln!(self.output.borrow_mut(), "{}", values.join(" "));
         }
-        Object::Nil
+        Ok(Object::Nil)
     }
 
-    fn visit_return_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_return_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::Return { keyword, value } = stmt {
             let value = if let Some(value) = value {
-                self.evaluate(value)
+                self.evaluate(value)?
             } else {
                 Object::Nil
             };
-            // Handle return value
-            // This might involve unwinding the stack or similar mechanism
+            return Err(Unwind::Return {
+                keyword: keyword.clone(),
+                value,
+            });
         }
-        Object::Nil
+        Ok(Object::Nil)
     }
 
-    fn visit_var_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_var_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::Var { name, initializer } = stmt {
             let value = if let Some(initializer) = initializer {
-                self.evaluate(initializer)
+                self.evaluate(initializer)?
             } else {
                 Object::Nil
             };
-            self.environment.define(name.lexeme.clone(), value);
+            self.environment.borrow_mut().define(name.lexeme.clone(), value);
+        }
+        Ok(Object::Nil)
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &Stmt) -> Outcome {
+        if let Stmt::While { condition, body, increment } = stmt {
+            while self.evaluate(condition)?.as_bool() {
+                match self.execute(body) {
+                    Ok(_) | Err(Unwind::Continue) => {}
+                    Err(Unwind::Break) => break,
+                    Err(unwind) => return Err(unwind),
+                }
+                if let Some(increment) = increment {
+                    self.evaluate(increment)?;
+                }
+            }
+        }
+        Ok(Object::Nil)
+    }
+
+    fn visit_break_stmt(&mut self, _stmt: &Stmt) -> Outcome {
+        Err(Unwind::Break)
+    }
+
+    fn visit_continue_stmt(&mut self, _stmt: &Stmt) -> Outcome {
+        Err(Unwind::Continue)
+    }
+
+    fn visit_test_stmt(&mut self, _stmt: &Stmt) -> Outcome {
+        // tests only run under `rusticle test`, which executes each body itself
+        Ok(Object::Nil)
+    }
+
+    fn visit_assert_stmt(&mut self, stmt: &Stmt) -> Outcome {
+        if let Stmt::Assert { keyword, condition } = stmt {
+            if !self.evaluate(condition)?.as_bool() {
+                return Err(RuntimeError::new(keyword, "Assertion failed.").into());
+            }
+            Ok(Object::Nil)
+        } else {
+            panic!("Expected assert statement")
         }
-        Object::Nil
     }
 
-    fn visit_while_stmt(&mut self, stmt: &Stmt) -> Object {
-        if let Stmt::While { condition, body } = stmt {
-            while self.evaluate(condition).as_bool() {
-                self.execute(body);
+    fn visit_assert_eq_stmt(&mut self, stmt: &Stmt) -> Outcome {
+        if let Stmt::AssertEq { keyword, left, right } = stmt {
+            let left = self.evaluate(left)?;
+            let right = self.evaluate(right)?;
+            if left != right {
+                let message = format!(
+                    "Assertion failed: left is {} but right is {}.",
+                    left.repr(),
+                    right.repr()
+                );
+                return Err(RuntimeError::new(keyword, &message).into());
             }
+            Ok(Object::Nil)
+        } else {
+            panic!("Expected assert_eq statement")
         }
-        Object::Nil
     }
 
-    fn visit_import_stmt(&mut self, stmt: &Stmt) -> Object {
+    fn visit_import_stmt(&mut self, stmt: &Stmt) -> Outcome {
         if let Stmt::Import {
+            keyword,
             function_name,
             package_name,
         } = stmt
         {
-            // Read rusticle.lock and load the required functions
-            let packages_lock_path: &str = "rusticle.lock";
-            let packages_lock: String =
-                if let Ok(content) = std::fs::read_to_string(packages_lock_path) {
-                    content
-                } else {
-                    let empty_lock: &str = "{\"packages\": []}";
-                    std::fs::write(packages_lock_path, empty_lock)
-                        .expect("Unable to create rusticle.lock");
-                    empty_lock.to_string()
-                };
-
-            let package_name: String = package_name.clone().trim_matches('"').to_string();
-            let function_name: String = function_name.clone().trim_matches('"').to_string();
+            let fail = |message: String| RuntimeError::new(keyword, &message);
 
+            // Read rusticle.lock and load the required functions
+            self.policy.check(Capability::Read).map_err(fail)?;
+            let create = self.policy.allows(Capability::Write);
             let packages_lock_data: PackagesLock =
-                serde_json::from_str(&packages_lock).expect("Unable to parse rusticle.lock");
+                read_packages_lock("rusticle.lock", create).map_err(fail)?;
 
-            if let Some(package) = packages_lock_data
+            let package: Package = match packages_lock_data
                 .packages
-                .iter()
-                .find(|p: &&Package| p.name == package_name)
+                .into_iter()
+                .find(|p: &Package| p.name == *package_name)
             {
-                if let Some(function) = package
-                    .functions
-                    .iter()
-                    .find(|f: &&Function| f.name.lexeme == function_name)
-                {
-                    self.environment
-                        .define(function_name.clone(), Object::Function(function.clone()));
-                } else {
-                    panic!(
-                        "Function '{}' not found in package '{}'",
-                        function_name, package_name
+                Some(package) => package,
+                None => {
+                    // Try to install the package
+                    self.policy.check(Capability::Install).map_err(|message| {
+                        fail(format!("Package '{}' is not installed. {}", package_name, message))
+                    })?;
+                    let _ = writeln!(
+                        self.output.borrow_mut(),
+                        "Package '{}' not found, attempting to install...",
+                        package_name
                     );
+                    let output = Rc::clone(&self.output);
+                    install_package(package_name, true, &mut *output.borrow_mut()).map_err(|message| {
+                        fail(format!("Couldn't install package '{}': {}", package_name, message))
+                    })?;
+
+                    // Read rusticle.temp.lock and load the required functions
+                    read_packages_lock("rusticle.temp.lock", false)
+                        .map_err(fail)?
+                        .packages
+                        .into_iter()
+                        .find(|p: &Package| p.name == *package_name)
+                        .ok_or_else(|| {
+                            fail(format!("Package '{}' not found after installation.", package_name))
+                        })?
                 }
-            } else {
-                // Try to install the package
-                println!(
-                    "Package '{}' not found, attempting to install...",
-                    package_name
-                );
-                let install_command = Install {
-                    package: package_name.clone(),
-                    temp: true,
-                };
-                install_command.execute();
-
-                // Read rusticle.temp.lock and load the required functions
-                let temp_packages_lock_path: &str = "rusticle.temp.lock";
-                let temp_packages_lock: String = std::fs::read_to_string(temp_packages_lock_path)
-                    .expect("Unable to read rusticle.temp.lock");
-
-                let temp_packages_lock_data: PackagesLock =
-                    serde_json::from_str(&temp_packages_lock)
-                        .expect("Unable to parse rusticle.temp.lock");
-
-                if let Some(package) = temp_packages_lock_data
-                    .packages
-                    .iter()
-                    .find(|p: &&Package| p.name == package_name)
-                {
-                    if let Some(function) = package
-                        .functions
-                        .iter()
-                        .find(|f: &&Function| f.name.lexeme == function_name)
-                    {
-                        self.environment
-                            .define(function_name.clone(), Object::Function(function.clone()));
-                    } else {
-                        panic!(
-                            "Function '{}' not found in package '{}'",
-                            function_name, package_name
-                        );
-                    }
-                } else {
-                    panic!("Package '{}' not found after installation", package_name);
-                }
-            }
+            };
+
+            let function: &Rc<Function> = package
+                .functions
+                .iter()
+                .find(|f: &&Rc<Function>| f.name.lexeme == *function_name)
+                .ok_or_else(|| {
+                    fail(format!(
+                        "Function '{}' not found in package '{}'.",
+                        function_name, package_name
+                    ))
+                })?;
+            self.load_function(keyword, function, package_name)?;
         }
-        Object::Nil
+        Ok(Object::Nil)
     }
 }
+
+/// Reads and parses a package lock file, first creating an empty one when
+/// `create` is set and the file doesn't exist yet.
+fn read_packages_lock(path: &str, create: bool) -> Result<PackagesLock, String> {
+    let content: String = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) if create => {
+            let empty_lock: &str = "{\"packages\": []}";
+            std::fs::write(path, empty_lock)
+                .map_err(|e| format!("Unable to create {}: {}", path, e))?;
+            empty_lock.to_string()
+        }
+        Err(e) => return Err(format!("Unable to read {}: {}", path, e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("Unable to parse {}: {}", path, e))
+}
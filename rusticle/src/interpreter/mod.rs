@@ -1,3 +1,8 @@
+#[allow(clippy::module_inception)]
 pub mod interpreter;
 pub mod visitor;
-pub mod environment;
\ No newline at end of file
+pub mod callable;
+pub mod environment;
+pub mod natives;
+pub mod operations;
+pub mod policy;
\ No newline at end of file
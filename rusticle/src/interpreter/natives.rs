@@ -0,0 +1,325 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::interpreter::environment::Environment;
+use crate::interpreter::interpreter::Interpreter;
+use crate::parser::ast::{Key, Native, Object};
+
+/// Defines the built-in functions every program starts with.
+pub fn define(globals: &mut Environment) {
+    let natives = [
+        Native::new("clock", Some(0), clock),
+        Native::new("len", Some(1), len),
+        Native::new("push", Some(2), push),
+        Native::new("pop", Some(1), pop),
+        Native::new("keys", Some(1), keys),
+        Native::new("values", Some(1), values),
+        Native::new("has", Some(2), has),
+        Native::new("remove", Some(2), remove),
+        Native::new("str", Some(1), str),
+        Native::new("num", Some(1), num),
+        Native::new("int", Some(1), int),
+        Native::new("float", Some(1), float),
+        Native::new("type", Some(1), type_of),
+        Native::new("input", None, input),
+        Native::new("format", None, format),
+    ];
+    for native in natives {
+        globals.define(native.name.clone(), Object::Native(Rc::new(native)));
+    }
+}
+
+/// Seconds since the Unix epoch, for timing code.
+fn clock(_interpreter: &mut Interpreter, _args: &[Object]) -> Result<Object, String> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("System clock is before the Unix epoch: {}.", e))?;
+    Ok(Object::Float(elapsed.as_secs_f64()))
+}
+
+/// Number of elements in a list, entries in a map or characters in a string.
+fn len(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::List(list) => Ok(Object::Int(list.borrow().len() as i64)),
+        Object::Map(map) => Ok(Object::Int(map.borrow().len() as i64)),
+        Object::String(s) => Ok(Object::Int(s.chars().count() as i64)),
+        other => Err(format!("len() expects a list, map or string but got {}.", other.type_name())),
+    }
+}
+
+/// Appends a value to the end of a list.
+fn push(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::List(list) => {
+            list.borrow_mut().push(args[1].clone());
+            Ok(Object::Nil)
+        }
+        other => Err(format!("push() expects a list but got {}.", other.type_name())),
+    }
+}
+
+/// Removes and returns the last element of a list.
+fn pop(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::List(list) => list.borrow_mut().pop().ok_or_else(|| "Can't pop from an empty list.".to_string()),
+        other => Err(format!("pop() expects a list but got {}.", other.type_name())),
+    }
+}
+
+/// The value as `likh` would print it.
+fn str(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    Ok(Object::String(args[0].to_string()))
+}
+
+/// Parses a string as an integer, or as a float when it isn't one;
+/// numbers are returned unchanged.
+fn num(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Int(_) | Object::Float(_) => Ok(args[0].clone()),
+        Object::String(s) => {
+            let text = s.trim();
+            text.parse()
+                .map(Object::Int)
+                .or_else(|_| text.parse().map(Object::Float))
+                .map_err(|_| format!("Can't convert '{}' to a number.", s))
+        }
+        other => Err(format!("num() expects a number or a string but got {}.", other.type_name())),
+    }
+}
+
+/// Converts to an integer, dropping any fractional part of a float.
+fn int(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Int(n) => Ok(Object::Int(*n)),
+        // `i64::MAX as f64` rounds up to 2^63, which is already out of range
+        Object::Float(n) if n.is_finite() && n.trunc() >= i64::MIN as f64 && n.trunc() < i64::MAX as f64 => {
+            Ok(Object::Int(n.trunc() as i64))
+        }
+        Object::Float(n) => Err(format!("Can't convert {:?} to an integer.", n)),
+        Object::Bool(b) => Ok(Object::Int(*b as i64)),
+        Object::String(s) => s
+            .trim()
+            .parse()
+            .map(Object::Int)
+            .map_err(|_| format!("Can't convert '{}' to an integer.", s)),
+        other => Err(format!("int() expects a number, bool or string but got {}.", other.type_name())),
+    }
+}
+
+fn float(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Int(n) => Ok(Object::Float(*n as f64)),
+        Object::Float(n) => Ok(Object::Float(*n)),
+        Object::String(s) => s
+            .trim()
+            .parse()
+            .map(Object::Float)
+            .map_err(|_| format!("Can't convert '{}' to a float.", s)),
+        other => Err(format!("float() expects a number or a string but got {}.", other.type_name())),
+    }
+}
+
+fn type_of(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    Ok(Object::String(args[0].type_name().to_string()))
+}
+
+/// The keys of a map as a list, in insertion order.
+fn keys(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Map(map) => Ok(list(map.borrow().keys().map(Key::to_object).collect())),
+        other => Err(format!("keys() expects a map but got {}.", other.type_name())),
+    }
+}
+
+/// The values of a map as a list, in insertion order.
+fn values(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Map(map) => Ok(list(map.borrow().values().cloned().collect())),
+        other => Err(format!("values() expects a map but got {}.", other.type_name())),
+    }
+}
+
+fn has(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Map(map) => Ok(Object::Bool(map.borrow().contains_key(&Key::from_object(&args[1])?))),
+        other => Err(format!("has() expects a map but got {}.", other.type_name())),
+    }
+}
+
+/// Removes an entry from a map and returns its value.
+fn remove(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match &args[0] {
+        Object::Map(map) => {
+            let key = Key::from_object(&args[1])?;
+            let removed = map.borrow_mut().shift_remove(&key);
+            removed.ok_or_else(|| format!("Key {} not found in map.", key))
+        }
+        other => Err(format!("remove() expects a map but got {}.", other.type_name())),
+    }
+}
+
+fn list(elements: Vec<Object>) -> Object {
+    Object::List(Rc::new(RefCell::new(elements)))
+}
+
+/// `input()` or `input(prompt)`: reads one line from the interpreter's input
+/// without its line ending, or returns `nil` once the input is exhausted.
+fn input(interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    match args {
+        [] => {}
+        [prompt] => {
+            let mut output = interpreter.output.borrow_mut();
+            write!(output, "{}", prompt)
+                .and_then(|_| output.flush())
+                .map_err(|e| format!("Unable to write prompt: {}.", e))?;
+        }
+        _ => return Err(format!("Expected at most 1 argument but got {}.", args.len())),
+    }
+
+    let mut line = String::new();
+    let read = interpreter
+        .input
+        .borrow_mut()
+        .read_line(&mut line)
+        .map_err(|e| format!("Unable to read input: {}.", e))?;
+    if read == 0 {
+        return Ok(Object::Nil);
+    }
+    let trimmed = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(trimmed);
+    Ok(Object::String(line))
+}
+
+/// `format(template, values...)` fills each `{}` in the template with the
+/// next value. A placeholder can carry a specifier after a colon,
+/// `{:[[fill]align][0][width][.precision]}`, and `{{` / `}}` stand for
+/// literal braces.
+fn format(_interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, String> {
+    let Some((Object::String(template), values)) = args.split_first() else {
+        return Err("format() takes a template string followed by the values to fill in.".to_string());
+    };
+
+    let mut values = values.iter();
+    let mut output = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                output.push('{');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest.find('}').ok_or("Unmatched '{' in format template.")?;
+                let placeholder = &rest[..end];
+                chars = rest[end + 1..].chars();
+
+                let spec = match placeholder.strip_prefix(':') {
+                    Some(spec) => Spec::parse(spec)?,
+                    None if placeholder.is_empty() => Spec::default(),
+                    None => return Err(format!("Invalid format placeholder '{{{}}}'.", placeholder)),
+                };
+                let value = values.next().ok_or("Not enough values for the format template.")?;
+                output.push_str(&spec.apply(value));
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => return Err("Unmatched '}' in format template.".to_string()),
+            c => output.push(c),
+        }
+    }
+
+    if values.next().is_some() {
+        return Err("More values than placeholders in the format template.".to_string());
+    }
+    Ok(Object::String(output))
+}
+
+#[derive(Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec, String> {
+        let invalid = || format!("Invalid format specifier '{}'.", spec);
+        let is_align = |c: char| matches!(c, '<' | '>' | '^');
+        let mut result = Spec::default();
+
+        let mut rest = spec;
+        let mut chars = spec.chars();
+        match (chars.next(), chars.next()) {
+            (Some(fill), Some(align)) if is_align(align) => {
+                result.fill = Some(fill);
+                result.align = Some(align);
+                rest = chars.as_str();
+            }
+            (Some(align), _) if is_align(align) => {
+                result.align = Some(align);
+                rest = &spec[1..];
+            }
+            _ => {}
+        }
+
+        if let Some(after) = rest.strip_prefix('0') {
+            result.zero = true;
+            rest = after;
+        }
+
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest, None),
+        };
+        // both are capped at u16::MAX, which is as far as Rust's own
+        // formatting goes before it panics
+        let number = |text: &str| text.parse::<u16>().map(usize::from).map_err(|_| invalid());
+        if !width.is_empty() {
+            result.width = number(width)?;
+        }
+        if let Some(precision) = precision {
+            result.precision = Some(number(precision)?);
+        }
+        Ok(result)
+    }
+
+    fn apply(&self, value: &Object) -> String {
+        let text = match (value, self.precision) {
+            (Object::Int(n), Some(precision)) => format!("{:.*}", precision, *n as f64),
+            (Object::Float(n), Some(precision)) => format!("{:.*}", precision, n),
+            (_, Some(precision)) => value.to_string().chars().take(precision).collect(),
+            (_, None) => value.to_string(),
+        };
+
+        let length = text.chars().count();
+        if length >= self.width {
+            return text;
+        }
+        let padding = self.width - length;
+
+        // zero padding goes between the sign and the digits
+        let is_number = matches!(value, Object::Int(_) | Object::Float(_));
+        if self.zero && self.align.is_none() && is_number {
+            let (sign, digits) = match text.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", text.as_str()),
+            };
+            return format!("{}{}{}", sign, "0".repeat(padding), digits);
+        }
+
+        let fill = self.fill.unwrap_or(' ').to_string();
+        let default_align = if is_number { '>' } else { '<' };
+        let (before, after) = match self.align.unwrap_or(default_align) {
+            '<' => (0, padding),
+            '>' => (padding, 0),
+            _ => (padding / 2, padding - padding / 2),
+        };
+        format!("{}{}{}", fill.repeat(before), text, fill.repeat(after))
+    }
+}
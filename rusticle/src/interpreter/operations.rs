@@ -0,0 +1,360 @@
+//! What the operators, indexing and slicing do to values, shared by the
+//! tree-walking interpreter and the bytecode VM so both behave the same.
+//! Errors point at the operator or bracket token passed in.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::parser::ast::{Key, Object};
+use crate::utils::error::RuntimeError;
+use crate::utils::token::{Token, TokenType};
+
+/// Operands of an arithmetic operator after numeric promotion.
+enum Numbers {
+    Int(i64, i64),
+    Float(f64, f64),
+}
+
+/// Applies a binary operator, including `==` and the comparisons.
+pub fn binary(operator: &Token, left: &Object, right: &Object) -> Result<Object, RuntimeError> {
+    if let TokenType::BangEqual | TokenType::EqualEqual = operator.token_type {
+        let equal = left == right;
+        return Ok(Object::Bool(equal == (operator.token_type == TokenType::EqualEqual)));
+    }
+
+    if let TokenType::Plus = operator.token_type {
+        match (left, right) {
+            (Object::String(l), Object::String(r)) => return Ok(Object::String(format!("{l}{r}"))),
+            (Object::Int(_) | Object::Float(_), Object::Int(_) | Object::Float(_)) => {}
+            _ => {
+                let message = "Operands must be two numbers or two strings.";
+                return Err(RuntimeError::new(operator, message));
+            }
+        }
+    }
+
+    if let TokenType::Greater
+    | TokenType::GreaterEqual
+    | TokenType::Less
+    | TokenType::LessEqual = operator.token_type
+    {
+        // NaN compares as unordered, which makes every comparison false
+        let ordering = compare_operands(operator, left, right)?;
+        return Ok(Object::Bool(match operator.token_type {
+            TokenType::Greater => ordering.is_some_and(Ordering::is_gt),
+            TokenType::GreaterEqual => ordering.is_some_and(Ordering::is_ge),
+            TokenType::Less => ordering.is_some_and(Ordering::is_lt),
+            _ => ordering.is_some_and(Ordering::is_le),
+        }));
+    }
+
+    match numeric_operands(operator, left, right)? {
+        Numbers::Int(l, r) => integer_arithmetic(operator, l, r),
+        Numbers::Float(l, r) => float_arithmetic(operator, l, r),
+    }
+}
+
+/// Applies a prefix operator.
+pub fn unary(operator: &Token, right: &Object) -> Result<Object, RuntimeError> {
+    Ok(match operator.token_type {
+        TokenType::Minus => match right {
+            Object::Int(n) => Object::Int(
+                n.checked_neg().ok_or_else(|| RuntimeError::new(operator, "Integer overflow."))?,
+            ),
+            Object::Float(n) => Object::Float(-n),
+            _ => return Err(RuntimeError::new(operator, "Operand must be a number.")),
+        },
+        TokenType::Bang => Object::Bool(!right.as_bool()),
+        TokenType::Tilde => match right {
+            Object::Int(n) => Object::Int(!n),
+            _ => return Err(RuntimeError::new(operator, "Operand must be an integer.")),
+        },
+        _ => panic!("Unexpected unary operator"),
+    })
+}
+
+/// Reads `object[index]` from a list, string or map.
+pub fn index(bracket: &Token, object: &Object, index: &Object) -> Result<Object, RuntimeError> {
+    match object {
+        Object::List(list) => {
+            let list = list.borrow();
+            Ok(list[check_index(bracket, index, list.len())?].clone())
+        }
+        Object::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let i = check_index(bracket, index, chars.len())?;
+            Ok(Object::String(chars[i].to_string()))
+        }
+        Object::Map(map) => {
+            let key = map_key(bracket, index)?;
+            map.borrow().get(&key).cloned().ok_or_else(|| {
+                let message = format!("Key {} not found in map.", key);
+                RuntimeError::new(bracket, &message)
+            })
+        }
+        _ => Err(RuntimeError::new(bracket, "Only lists, strings and maps can be indexed.")),
+    }
+}
+
+/// Performs `object[index] = value` on a list or map.
+pub fn index_set(bracket: &Token, object: &Object, index: &Object, value: Object) -> Result<(), RuntimeError> {
+    match object {
+        Object::List(list) => {
+            let mut list = list.borrow_mut();
+            let i = check_index(bracket, index, list.len())?;
+            list[i] = value;
+        }
+        // assigning to a missing key adds it
+        Object::Map(map) => {
+            let key = map_key(bracket, index)?;
+            map.borrow_mut().insert(key, value);
+        }
+        _ => {
+            let message = "Only list elements and map entries can be assigned.";
+            return Err(RuntimeError::new(bracket, message));
+        }
+    }
+    Ok(())
+}
+
+/// Copies `object[start:end]` out of a list or string.
+pub fn slice(
+    bracket: &Token,
+    object: &Object,
+    start: Option<&Object>,
+    end: Option<&Object>,
+) -> Result<Object, RuntimeError> {
+    match object {
+        Object::List(list) => {
+            let list = list.borrow();
+            let range = check_slice(bracket, start, end, list.len())?;
+            Ok(Object::List(Rc::new(RefCell::new(list[range].to_vec()))))
+        }
+        Object::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let range = check_slice(bracket, start, end, chars.len())?;
+            Ok(Object::String(chars[range].iter().collect()))
+        }
+        _ => Err(RuntimeError::new(bracket, "Only lists and strings can be sliced.")),
+    }
+}
+
+pub fn map_key(token: &Token, key: &Object) -> Result<Key, RuntimeError> {
+    Key::from_object(key).map_err(|message| RuntimeError::new(token, &message))
+}
+
+/// Checks that `args` fits the callee's arity, reporting at the call's `)`.
+pub fn check_arity(paren: &Token, arity: usize, args: usize) -> Result<(), RuntimeError> {
+    if args != arity {
+        let message = format!("Expected {} arguments but got {}.", arity, args);
+        return Err(RuntimeError::new(paren, &message));
+    }
+    Ok(())
+}
+
+/// Promotes the operands of an arithmetic operator: two integers stay
+/// integers, and an integer mixed with a float becomes a float.
+fn numeric_operands(operator: &Token, left: &Object, right: &Object) -> Result<Numbers, RuntimeError> {
+    match (left, right) {
+        (Object::Int(l), Object::Int(r)) => Ok(Numbers::Int(*l, *r)),
+        (Object::Int(l), Object::Float(r)) => Ok(Numbers::Float(*l as f64, *r)),
+        (Object::Float(l), Object::Int(r)) => Ok(Numbers::Float(*l, *r as f64)),
+        (Object::Float(l), Object::Float(r)) => Ok(Numbers::Float(*l, *r)),
+        _ => Err(RuntimeError::new(operator, "Operands must be numbers.")),
+    }
+}
+
+fn integer_arithmetic(operator: &Token, l: i64, r: i64) -> Result<Object, RuntimeError> {
+    let overflow = || RuntimeError::new(operator, "Integer overflow.");
+    let divisor = || {
+        if r == 0 {
+            Err(RuntimeError::new(operator, "Division by zero."))
+        } else {
+            Ok(r)
+        }
+    };
+
+    Ok(match operator.token_type {
+        TokenType::Plus => Object::Int(l.checked_add(r).ok_or_else(overflow)?),
+        TokenType::Minus => Object::Int(l.checked_sub(r).ok_or_else(overflow)?),
+        TokenType::Star => Object::Int(l.checked_mul(r).ok_or_else(overflow)?),
+        // true division, so `7 / 2` is 3.5 rather than silently truncated
+        TokenType::Slash => Object::Float(l as f64 / r as f64),
+        TokenType::Mod => {
+            let r = divisor()?;
+            let remainder = l.wrapping_rem(r);
+            if remainder != 0 && (remainder < 0) != (r < 0) {
+                Object::Int(remainder + r)
+            } else {
+                Object::Int(remainder)
+            }
+        }
+        TokenType::TildeSlash => {
+            let r = divisor()?;
+            let quotient = l.checked_div(r).ok_or_else(overflow)?;
+            if l % r != 0 && (l < 0) != (r < 0) {
+                Object::Int(quotient - 1)
+            } else {
+                Object::Int(quotient)
+            }
+        }
+        TokenType::StarStar => match u32::try_from(r) {
+            Ok(exponent) => Object::Int(l.checked_pow(exponent).ok_or_else(overflow)?),
+            // a negative power is a fraction
+            Err(_) => Object::Float((l as f64).powf(r as f64)),
+        },
+        TokenType::Ampersand => Object::Int(l & r),
+        TokenType::Pipe => Object::Int(l | r),
+        TokenType::Caret => Object::Int(l ^ r),
+        TokenType::LessLess | TokenType::GreaterGreater => {
+            let shift = u32::try_from(r)
+                .ok()
+                .filter(|shift| *shift < i64::BITS)
+                .ok_or_else(|| RuntimeError::new(operator, "Shift amount must be between 0 and 63."))?;
+            if operator.token_type == TokenType::LessLess {
+                Object::Int(l << shift)
+            } else {
+                Object::Int(l >> shift)
+            }
+        }
+        _ => panic!("Unexpected binary operator"),
+    })
+}
+
+fn float_arithmetic(operator: &Token, l: f64, r: f64) -> Result<Object, RuntimeError> {
+    Ok(Object::Float(match operator.token_type {
+        TokenType::Plus => l + r,
+        TokenType::Minus => l - r,
+        TokenType::Star => l * r,
+        TokenType::Slash => l / r,
+        TokenType::Mod => {
+            check_divisor(operator, r)?;
+            // floored, so the result takes the sign of the divisor like `~/`
+            let remainder = l % r;
+            if remainder != 0.0 && (remainder < 0.0) != (r < 0.0) {
+                remainder + r
+            } else {
+                remainder
+            }
+        }
+        TokenType::TildeSlash => {
+            check_divisor(operator, r)?;
+            (l / r).floor()
+        }
+        TokenType::StarStar => l.powf(r),
+        TokenType::Ampersand
+        | TokenType::Pipe
+        | TokenType::Caret
+        | TokenType::LessLess
+        | TokenType::GreaterGreater => {
+            return Err(RuntimeError::new(operator, "Operands must be integers."));
+        }
+        _ => panic!("Unexpected binary operator"),
+    }))
+}
+
+/// Turns an index into a position inside a sequence of `len` elements.
+fn check_index(bracket: &Token, index: &Object, len: usize) -> Result<usize, RuntimeError> {
+    let position = position(bracket, index)?;
+    usize::try_from(position)
+        .ok()
+        .filter(|position| *position < len)
+        .ok_or_else(|| {
+            let message = format!("Index {} is out of range for length {}.", position, len);
+            RuntimeError::new(bracket, &message)
+        })
+}
+
+/// Turns slice bounds into a range; a missing start or end means the
+/// beginning or end of the sequence.
+fn check_slice(
+    bracket: &Token,
+    start: Option<&Object>,
+    end: Option<&Object>,
+    len: usize,
+) -> Result<std::ops::Range<usize>, RuntimeError> {
+    let bound = |bound: Option<&Object>, default: usize| -> Result<usize, RuntimeError> {
+        let Some(bound) = bound else {
+            return Ok(default);
+        };
+        let position = position(bracket, bound)?;
+        usize::try_from(position)
+            .ok()
+            .filter(|position| *position <= len)
+            .ok_or_else(|| {
+                let message = format!("Slice bound {} is out of range for length {}.", position, len);
+                RuntimeError::new(bracket, &message)
+            })
+    };
+    let start = bound(start, 0)?;
+    let end = bound(end, len)?;
+
+    if start > end {
+        let message = format!("Slice start {} is after its end {}.", start, end);
+        return Err(RuntimeError::new(bracket, &message));
+    }
+    Ok(start..end)
+}
+
+fn position(bracket: &Token, index: &Object) -> Result<i64, RuntimeError> {
+    match index {
+        Object::Int(n) => Ok(*n),
+        _ => Err(RuntimeError::new(bracket, "Index must be an integer.")),
+    }
+}
+
+/// Float `%` and `~/` have no sensible answer for a zero divisor,
+/// unlike `/` which follows floating point and gives infinity.
+fn check_divisor(operator: &Token, divisor: f64) -> Result<(), RuntimeError> {
+    if divisor == 0.0 {
+        Err(RuntimeError::new(operator, "Division by zero."))
+    } else {
+        Ok(())
+    }
+}
+
+/// Orders two numbers numerically or two strings lexicographically.
+/// Mixing types is an error rather than a coercion.
+fn compare_operands(
+    operator: &Token,
+    left: &Object,
+    right: &Object,
+) -> Result<Option<Ordering>, RuntimeError> {
+    match (left, right) {
+        (Object::String(l), Object::String(r)) => Ok(Some(l.cmp(r))),
+        // exact, as `==` is, rather than rounding the integer to a float
+        (Object::Int(i), Object::Float(n)) => Ok(compare_int_float(*i, *n)),
+        (Object::Float(n), Object::Int(i)) => Ok(compare_int_float(*i, *n).map(Ordering::reverse)),
+        _ => match numeric_operands(operator, left, right) {
+            Ok(Numbers::Int(l, r)) => Ok(Some(l.cmp(&r))),
+            Ok(Numbers::Float(l, r)) => Ok(l.partial_cmp(&r)),
+            Err(_) => Err(RuntimeError::new(
+                operator,
+                "Operands must be two numbers or two strings.",
+            )),
+        },
+    }
+}
+
+/// Orders an integer against a float by their exact values. Converting the
+/// integer would round anything past 2^53, making `9007199254740993` equal
+/// to `9007199254740992.0`.
+fn compare_int_float(i: i64, n: f64) -> Option<Ordering> {
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0; // 2^63
+    if n.is_nan() {
+        return None;
+    }
+    if n >= LIMIT {
+        return Some(Ordering::Less);
+    }
+    if n < -LIMIT {
+        return Some(Ordering::Greater);
+    }
+    // the whole part is exact as an integer here; the fraction breaks ties
+    let whole = n.trunc();
+    match i.cmp(&(whole as i64)) {
+        Ordering::Equal => 0.0.partial_cmp(&(n - whole)),
+        ordering => Some(ordering),
+    }
+}
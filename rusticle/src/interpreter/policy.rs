@@ -0,0 +1,84 @@
+use std::fmt;
+
+/// Something outside the interpreter a program may want to touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capability {
+    /// Reading files, such as `rusticle.lock` when importing.
+    Read,
+    /// Creating or changing files.
+    Write,
+    /// Making network requests.
+    Net,
+    /// Fetching a package that `import` can't find. Also needs `Write` and
+    /// `Net`, since installing downloads the package into a lock file.
+    Install,
+}
+
+impl Capability {
+    /// The command-line flag that grants this capability.
+    fn flag(self) -> &'static str {
+        match self {
+            Capability::Read => "--allow-read",
+            Capability::Write => "--allow-write",
+            Capability::Net => "--allow-net",
+            Capability::Install => "--allow-write --allow-net",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::Read => "Reading files",
+            Capability::Write => "Writing files",
+            Capability::Net => "Network access",
+            Capability::Install => "Installing packages",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The capabilities a program is granted. Everything is denied by default,
+/// so a program can only compute and talk through its input and output.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Policy {
+    pub read: bool,
+    pub write: bool,
+    pub net: bool,
+    pub install: bool,
+}
+
+impl Policy {
+    /// Grants every capability.
+    pub fn allow_all() -> Self {
+        Policy {
+            read: true,
+            write: true,
+            net: true,
+            install: true,
+        }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Read => self.read,
+            Capability::Write => self.write,
+            Capability::Net => self.net,
+            Capability::Install => self.install && self.write && self.net,
+        }
+    }
+
+    /// Fails with a message naming the flag to pass when `capability` is
+    /// denied.
+    pub fn check(&self, capability: Capability) -> Result<(), String> {
+        if self.allows(capability) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} is not allowed; run with {} to permit it.",
+                capability,
+                capability.flag()
+            ))
+        }
+    }
+}
@@ -4,9 +4,21 @@ pub trait Visitor<R> {
     fn visit_assign_expr(&mut self, expr: &Expr) -> R;
     fn visit_binary_expr(&mut self, expr: &Expr) -> R;
     fn visit_call_expr(&mut self, expr: &Expr) -> R;
+    fn visit_compound_set_expr(&mut self, expr: &Expr) -> R;
+    fn visit_compound_index_set_expr(&mut self, expr: &Expr) -> R;
+    fn visit_get_expr(&mut self, expr: &Expr) -> R;
     fn visit_grouping_expr(&mut self, expr: &Expr) -> R;
+    fn visit_index_expr(&mut self, expr: &Expr) -> R;
+    fn visit_index_set_expr(&mut self, expr: &Expr) -> R;
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> R;
+    fn visit_list_expr(&mut self, expr: &Expr) -> R;
+    fn visit_map_expr(&mut self, expr: &Expr) -> R;
     fn visit_literal_expr(&mut self, expr: &Expr) -> R;
     fn visit_logical_expr(&mut self, expr: &Expr) -> R;
+    fn visit_set_expr(&mut self, expr: &Expr) -> R;
+    fn visit_slice_expr(&mut self, expr: &Expr) -> R;
+    fn visit_super_expr(&mut self, expr: &Expr) -> R;
+    fn visit_this_expr(&mut self, expr: &Expr) -> R;
     fn visit_unary_expr(&mut self, expr: &Expr) -> R;
     fn visit_variable_expr(&mut self, expr: &Expr) -> R;
 
@@ -19,5 +31,10 @@ pub trait Visitor<R> {
     fn visit_return_stmt(&mut self, stmt: &Stmt) -> R;
     fn visit_var_stmt(&mut self, stmt: &Stmt) -> R;
     fn visit_while_stmt(&mut self, stmt: &Stmt) -> R;
+    fn visit_break_stmt(&mut self, stmt: &Stmt) -> R;
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) -> R;
+    fn visit_test_stmt(&mut self, stmt: &Stmt) -> R;
+    fn visit_assert_stmt(&mut self, stmt: &Stmt) -> R;
+    fn visit_assert_eq_stmt(&mut self, stmt: &Stmt) -> R;
     fn visit_import_stmt(&mut self, stmt: &Stmt) -> R;
 }
\ No newline at end of file
@@ -1,12 +1,27 @@
+use crate::utils::diagnostic::Span;
+use crate::utils::error::LexError;
 use crate::utils::token::{Token, TokenType};
 use std::collections::HashMap;
 
 pub struct Lexer {
     source: Vec<char>,
     tokens: Vec<Token>,
+    errors: Vec<LexError>,
     start: usize,
     current: usize,
+    /// Byte offsets matching `start` and `current`.
+    start_byte: usize,
+    current_byte: usize,
     line: usize,
+    /// Index of the first character on the current line.
+    line_start: usize,
+    /// Where the token being scanned begins, for error reporting.
+    start_line: usize,
+    start_column: usize,
+    /// One entry per `${` we are inside: the braces opened since, so the
+    /// matching `}` can hand control back to the string, and where the
+    /// string part began in case it never gets closed.
+    interpolations: Vec<(usize, Span)>,
     keywords: HashMap<String, TokenType>,
 }
 
@@ -29,6 +44,11 @@ impl Lexer {
         keywords.insert("true".to_string(), TokenType::True);
         keywords.insert("manle".to_string(), TokenType::Var);
         keywords.insert("jabTak".to_string(), TokenType::While);
+        keywords.insert("ruk".to_string(), TokenType::Break);
+        keywords.insert("agla".to_string(), TokenType::Continue);
+        keywords.insert("test".to_string(), TokenType::Test);
+        keywords.insert("assert".to_string(), TokenType::Assert);
+        keywords.insert("assert_eq".to_string(), TokenType::AssertEq);
         keywords.insert("import".to_string(), TokenType::Import); // Add import keyword
         keywords.insert("from".to_string(), TokenType::From); // Add import keyword
 
@@ -36,21 +56,48 @@ impl Lexer {
         Lexer {
             source: source.chars().collect(),
             tokens: Vec::new(),
+            errors: Vec::new(),
             start: 0,
             current: 0,
+            start_byte: 0,
+            current_byte: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
             keywords,
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Result<&Vec<Token>, String> {
+    /// Scans the whole source. Scanning carries on past bad input so that
+    /// every lexical error in the file is reported at once.
+    pub fn scan_tokens(&mut self) -> Result<&Vec<Token>, Vec<LexError>> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_byte = self.current_byte;
+            self.start_line = self.line;
+            self.start_column = self.start - self.line_start + 1;
             self.scan_token();
         }
 
-        self.tokens.push(Token::new(TokenType::Eof, String::new(), self.line));
-        Ok(&self.tokens)
+        for (_, span) in std::mem::take(&mut self.interpolations) {
+            self.error_at("Unterminated string interpolation.".to_string(), span);
+        }
+
+        self.tokens.push(Token {
+            token_type: TokenType::Eof,
+            lexeme: String::new(),
+            line: self.line,
+            offset: self.current_byte,
+            column: self.current - self.line_start + 1,
+            length: 0,
+        });
+        if self.errors.is_empty() {
+            Ok(&self.tokens)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
 
     fn scan_token(&mut self) {
@@ -58,15 +105,65 @@ impl Lexer {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            },
+            '}' => match self.interpolations.last_mut() {
+                // closes a `${`, so the rest belongs to the string again
+                Some((0, _)) => {
+                    self.interpolations.pop();
+                    self.string(TokenType::InterpolationEnd);
+                },
+                Some((depth, _)) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace);
+                },
+                None => self.add_token(TokenType::RightBrace),
+            },
+            '#' if self.match_char('{') => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::HashBrace);
+            },
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
+            ':' => self.add_token(TokenType::Colon),
             '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
-            '%' => self.add_token(TokenType::Mod),
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '-' => {
+                let token: TokenType = if self.match_char('=') { TokenType::MinusEqual } else { TokenType::Minus };
+                self.add_token(token);
+            },
+            '+' => {
+                let token: TokenType = if self.match_char('=') { TokenType::PlusEqual } else { TokenType::Plus };
+                self.add_token(token);
+            },
+            '%' => {
+                let token: TokenType = if self.match_char('=') { TokenType::ModEqual } else { TokenType::Mod };
+                self.add_token(token);
+            },
+            '~' => {
+                let token: TokenType = if self.match_char('/') { TokenType::TildeSlash } else { TokenType::Tilde };
+                self.add_token(token);
+            },
+            '*' => {
+                let token: TokenType = if self.match_char('*') {
+                    TokenType::StarStar
+                } else if self.match_char('=') {
+                    TokenType::StarEqual
+                } else {
+                    TokenType::Star
+                };
+                self.add_token(token);
+            },
             '!' => {
                 let token: TokenType = if self.match_char('=') { TokenType::BangEqual } else { TokenType::Bang };
                 self.add_token(token);
@@ -76,11 +173,23 @@ impl Lexer {
                 self.add_token(token);
             },
             '<' => {
-                let token: TokenType = if self.match_char('=') { TokenType::LessEqual } else { TokenType::Less };
+                let token: TokenType = if self.match_char('=') {
+                    TokenType::LessEqual
+                } else if self.match_char('<') {
+                    TokenType::LessLess
+                } else {
+                    TokenType::Less
+                };
                 self.add_token(token);
             },
             '>' => {
-                let token: TokenType = if self.match_char('=') { TokenType::GreaterEqual } else { TokenType::Greater };
+                let token: TokenType = if self.match_char('=') {
+                    TokenType::GreaterEqual
+                } else if self.match_char('>') {
+                    TokenType::GreaterGreater
+                } else {
+                    TokenType::Greater
+                };
                 self.add_token(token);
             },
             '/' => {
@@ -88,16 +197,18 @@ impl Lexer {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::SlashEqual);
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             },
             ' ' | '\r' | '\t' => {},
-            '\n' => self.line += 1,
-            '"' => self.string(),
+            '\n' => self.newline(),
+            '"' => self.string(TokenType::String),
             c if c.is_ascii_digit() => self.number(),
             c if c.is_ascii_alphabetic() || c == '_' => self.identifier(),
-            _ => self.error_token(format!("Unexpected character: {}", c)),
+            _ => self.error(format!("Unexpected character '{}'.", c)),
         }
     }
 
@@ -127,22 +238,93 @@ impl Lexer {
         self.add_token(TokenType::Number);
     }
 
-    fn string(&mut self) {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+    /// Scans string text up to the closing quote, or up to a `${` which
+    /// ends this part as an `Interpolation` token and lets the embedded
+    /// expression be scanned as ordinary tokens. Text that runs to the
+    /// closing quote becomes a `last` token: a `String`, or an
+    /// `InterpolationEnd` when it follows an interpolated expression.
+    fn string(&mut self, last: TokenType) {
+        let mut value = String::new();
+        loop {
+            if self.is_at_end() {
+                self.error("Unterminated string.".to_string());
+                return;
             }
-            self.advance();
-        }
 
-        if self.is_at_end() {
-            self.error_token("Unterminated string.".to_string());
-            return;
+            match self.advance() {
+                '"' => break,
+                '\\' => {
+                    if let Some(c) = self.escape() {
+                        value.push(c);
+                    }
+                },
+                '$' if self.peek() == '{' => {
+                    self.advance();
+                    self.add_string_token(TokenType::Interpolation, value);
+                    let span = self.tokens.last().map(Token::span).expect("token was just added");
+                    self.interpolations.push((0, span));
+                    return;
+                },
+                c => {
+                    if c == '\n' {
+                        self.newline();
+                    }
+                    value.push(c);
+                },
+            }
         }
 
-        self.advance();
-        let _value: String = self.source[self.start + 1..self.current - 1].iter().collect();
-        self.add_token(TokenType::String);
+        self.add_string_token(last, value);
+    }
+
+    /// Decodes the escape sequence following a backslash, reporting it
+    /// and carrying on with the rest of the string when it isn't valid.
+    fn escape(&mut self) -> Option<char> {
+        let start = self.current - 1;
+        let start_byte = self.current_byte - 1;
+        let column = start - self.line_start + 1;
+
+        let c = self.peek();
+        let decoded = if self.is_at_end() || c == '\n' {
+            None
+        } else {
+            self.advance();
+            match c {
+                'n' => Some('\n'),
+                't' => Some('\t'),
+                'r' => Some('\r'),
+                '0' => Some('\0'),
+                '"' => Some('"'),
+                '\\' => Some('\\'),
+                '$' => Some('$'),
+                'u' if self.match_char('{') => {
+                    let mut digits = String::new();
+                    while self.peek().is_ascii_hexdigit() {
+                        digits.push(self.advance());
+                    }
+                    if self.match_char('}') {
+                        u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32)
+                    } else {
+                        None
+                    }
+                },
+                _ => None,
+            }
+        };
+
+        if decoded.is_none() {
+            let sequence: String = self.source[start..self.current].iter().collect();
+            self.error_at(
+                format!("Invalid escape sequence '{}'.", sequence),
+                Span {
+                    line: self.line,
+                    column,
+                    offset: start_byte,
+                    length: self.current_byte - start_byte,
+                },
+            );
+        }
+        decoded
     }
 
     fn match_char(&mut self, expected: char) -> bool {
@@ -150,6 +332,7 @@ impl Lexer {
             false
         } else {
             self.current += 1;
+            self.current_byte += expected.len_utf8();
             true
         }
     }
@@ -177,15 +360,51 @@ impl Lexer {
     fn advance(&mut self) -> char {
         let c = self.source[self.current];
         self.current += 1;
+        self.current_byte += c.len_utf8();
         c
     }
 
     fn add_token(&mut self, token_type: TokenType) {
         let lexeme: String = self.source[self.start..self.current].iter().collect();
-        self.tokens.push(Token::new(token_type, lexeme, self.line));
+        self.tokens.push(Token {
+            token_type,
+            lexeme,
+            line: self.start_line,
+            offset: self.start_byte,
+            column: self.start_column,
+            length: self.current_byte - self.start_byte,
+        });
+    }
+
+    /// Pushes a string token whose lexeme is the decoded text rather than
+    /// the quoted source; the span still covers the source it came from.
+    fn add_string_token(&mut self, token_type: TokenType, value: String) {
+        self.tokens.push(Token {
+            token_type,
+            lexeme: value,
+            line: self.start_line,
+            offset: self.start_byte,
+            column: self.start_column,
+            length: self.current_byte - self.start_byte,
+        });
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    fn error(&mut self, message: String) {
+        let span = Span {
+            line: self.start_line,
+            column: self.start_column,
+            offset: self.start_byte,
+            length: self.current_byte - self.start_byte,
+        };
+        self.error_at(message, span);
     }
 
-    fn error_token(&mut self, message: String) {
-        self.tokens.push(Token::new(TokenType::Error(message), String::new(), self.line));
+    fn error_at(&mut self, message: String, span: Span) {
+        self.errors.push(LexError { message, span });
     }
 }
\ No newline at end of file
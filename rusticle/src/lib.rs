@@ -0,0 +1,33 @@
+//! Lin as a library, for embedding the language in Rust programs.
+//!
+//! An [`Engine`] holds one interpreter. Every script it runs shares the same
+//! globals, so values set from Rust are visible to scripts and definitions
+//! made by one script are visible to the next:
+//!
+//! ```
+//! use rusticle::{Engine, Object};
+//!
+//! let mut engine = Engine::new();
+//! engine.set_global("limit", Object::Int(10));
+//! engine.register_fn("double", |args| match args {
+//!     [Object::Int(n)] => Ok(Object::Int(n * 2)),
+//!     _ => Err("double() expects one integer.".to_string()),
+//! });
+//!
+//! let value = engine.eval("double(limit) + 1").unwrap();
+//! assert_eq!(value, Object::Int(21));
+//! ```
+
+pub mod commands;
+pub mod engine;
+pub mod interpreter;
+pub mod lexer;
+pub mod optimizer;
+pub mod parser;
+pub mod resolver;
+pub mod utils;
+pub mod vm;
+
+pub use engine::{Engine, Error};
+pub use interpreter::policy::{Capability, Policy};
+pub use parser::ast::Object;
@@ -1,22 +1,31 @@
-mod commands;
-mod lexer;
-mod parser;
-mod utils;
-mod interpreter;
-
-use crate::commands::execute::Interpret;
-use crate::commands::reject::Invalid;
-use crate::commands::install::Install;
+use rusticle::commands::execute::Interpret;
+use rusticle::commands::reject::Invalid;
+use rusticle::commands::install::Install;
+use rusticle::commands::repl::Repl;
+use rusticle::commands::test::Test;
 use clap::{Arg, Command as ClapCommand};
-use commands::Command;
+use rusticle::commands::Command;
+use rusticle::interpreter::interpreter::STACK_SIZE;
+use rusticle::interpreter::policy::Policy;
+use std::thread;
 
 fn main() {
+    // every call the tree-walker makes recurses on the native stack
+    let runner = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("Unable to start the interpreter thread");
+    if let Err(panic) = runner.join() {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+fn run() {
     let matches: clap::ArgMatches = ClapCommand::new("Rusticle")
         .about("Custom Lin language interpreter")
         .arg(
             Arg::new("arg")
-                .required(true)
-                .help("The .lin file to interpret"),
+                .help("The .lin file to interpret; starts a REPL when left out"),
         )
         .arg(
             Arg::new("tokens")
@@ -36,14 +45,70 @@ fn main() {
                 .help("Show raw AST statements")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("opt")
+                .long("opt")
+                .help("Optimize the program before running it")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("engine")
+                .long("engine")
+                .value_name("ENGINE")
+                .value_parser(["tree", "vm"])
+                .default_value("tree")
+                .help("Run on the tree-walking interpreter or the bytecode VM"),
+        )
+        .arg(
+            Arg::new("disassemble")
+                .long("disassemble")
+                .help("Show the compiled bytecode")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-read")
+                .long("allow-read")
+                .help("Let the program read files, such as rusticle.lock")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-write")
+                .long("allow-write")
+                .help("Let the program create and change files")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-net")
+                .long("allow-net")
+                .help("Let the program make network requests")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .value_name("NAME")
+                .help("With test, only run tests whose name contains NAME"),
+        )
         .arg(
             Arg::new("install")
                 .value_name("PACKAGE")
-                .help("Install a specific package.")
+                .num_args(0..)
+                .help("Install a specific package, or the files and directories to test.")
         )
         .get_matches();
 
-        let arg: &String = matches.get_one::<String>("arg").unwrap();
+        // missing packages are only installed when both writing and the network are allowed
+        let policy = Policy {
+            read: matches.get_flag("allow-read"),
+            write: matches.get_flag("allow-write"),
+            net: matches.get_flag("allow-net"),
+            install: true,
+        };
+
+        let Some(arg) = matches.get_one::<String>("arg") else {
+            Repl { policy }.execute();
+            return;
+        };
 
         let command: Box<dyn Command> = if arg.ends_with(".lin") {
             Box::new(Interpret {
@@ -60,11 +125,30 @@ fn main() {
                     true => {Some(true)},
                     false => None,
                 },
+                opt: match matches.get_flag("opt") {
+                    true => {Some(true)},
+                    false => None,
+                },
+                vm: match matches.get_one::<String>("engine").map(String::as_str) {
+                    Some("vm") => {Some(true)},
+                    _ => None,
+                },
+                disassemble: match matches.get_flag("disassemble") {
+                    true => {Some(true)},
+                    false => None,
+                },
+                policy,
+            })
+        } else if arg == "test" {
+            Box::new(Test {
+                paths: matches.get_many::<String>("install").unwrap_or_default().cloned().collect(),
+                filter: matches.get_one::<String>("filter").cloned(),
+                policy,
             })
         } else if arg == "install"{
-            if matches.get_one::<String>("install").is_some() {
+            if let Some(package) = matches.get_many::<String>("install").and_then(|mut values| values.next()) {
                 Box::new(Install {
-                    package: matches.get_one::<String>("install").unwrap().clone(),
+                    package: package.clone(),
                     temp: false,
                 })
             } else {
@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::interpreter::operations;
+use crate::parser::ast::{Expr, Object, Stmt};
+
+/// Rewrites a parsed program into a cheaper one that behaves the same. The
+/// program must already have resolved without errors, since dropping a dead
+/// branch also drops any static errors inside it. The output has to be
+/// resolved again before it runs, so it only needs to keep the tree well
+/// formed, not its scope depths.
+///
+/// - `Binary`, `Unary` and `Grouping` expressions over literals are folded
+///   into a literal. Ones that would fail, like `1 ~/ 0`, are left alone so
+///   the error is still raised when the program runs.
+/// - An `agar` whose condition folds to a literal is replaced by the branch
+///   that would run, or dropped when there is none.
+/// - Reads of a `manle` initialized with a literal become that literal, as
+///   long as nothing ever assigns to the name.
+pub struct Optimizer {
+    /// One map per scope, from each name declared in it to its value when
+    /// that value is a constant the optimizer may inline.
+    scopes: Vec<HashMap<String, Option<Object>>>,
+    /// Names that are assigned somewhere or declared at the top level more
+    /// than once, and so are never treated as constants.
+    unstable: HashSet<String>,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Optimizer::new()
+    }
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Optimizer {
+            scopes: vec![HashMap::new()],
+            unstable: HashSet::new(),
+        }
+    }
+
+    pub fn optimize(mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        // a global can be declared again, which changes it like an assignment
+        let mut globals = HashSet::new();
+        for statement in &statements {
+            let name = match statement {
+                Stmt::Var { name, .. } | Stmt::Function { name, .. } | Stmt::Class { name, .. } => {
+                    name.lexeme.clone()
+                }
+                Stmt::Import { function_name, .. } => function_name.clone(),
+                _ => continue,
+            };
+            if !globals.insert(name.clone()) {
+                self.unstable.insert(name);
+            }
+        }
+        for statement in &statements {
+            assignments_in_stmt(statement, &mut self.unstable);
+        }
+
+        self.statements(statements)
+    }
+
+    fn statements(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        statements
+            .into_iter()
+            .filter_map(|statement| self.stmt(statement))
+            .collect()
+    }
+
+    /// Optimizes the statements in a new scope.
+    fn block(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        self.scopes.push(HashMap::new());
+        let statements = self.statements(statements);
+        self.scopes.pop();
+        statements
+    }
+
+    fn declare(&mut self, name: &str, value: Option<Object>) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), value);
+    }
+
+    /// The constant `name` refers to at this point, if it is one.
+    fn constant(&self, name: &str) -> Option<Object> {
+        let scope = self.scopes.iter().rev().find(|scope| scope.contains_key(name))?;
+        scope[name].clone()
+    }
+
+    /// Like `stmt` for a statement that has to stay a statement, such as
+    /// the body of a loop.
+    fn nested(&mut self, stmt: Stmt) -> Stmt {
+        self.stmt(stmt).unwrap_or(Stmt::Block { statements: Vec::new() })
+    }
+
+    /// Optimizes a statement, returning `None` when it can be dropped.
+    fn stmt(&mut self, stmt: Stmt) -> Option<Stmt> {
+        Some(match stmt {
+            Stmt::Block { statements } => Stmt::Block { statements: self.block(statements) },
+            Stmt::Class { name, superclass, methods } => {
+                self.declare(&name.lexeme, None);
+                // methods are reached through instances, never by name
+                self.scopes.push(HashMap::new());
+                let methods = methods.into_iter().map(|method| self.nested(method)).collect();
+                self.scopes.pop();
+                Stmt::Class { name, superclass, methods }
+            }
+            Stmt::Expression { expression } => Stmt::Expression { expression: self.expr(expression) },
+            Stmt::Function { name, params, body } => {
+                // declared first so the body can call itself
+                self.declare(&name.lexeme, None);
+                self.scopes.push(params.iter().map(|param| (param.lexeme.clone(), None)).collect());
+                let body = self.statements(body);
+                self.scopes.pop();
+                Stmt::Function { name, params, body }
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                let condition = self.expr(condition);
+                if let Expr::Literal { value } = &condition {
+                    return if value.as_bool() {
+                        self.stmt(*then_branch)
+                    } else {
+                        else_branch.and_then(|else_branch| self.stmt(*else_branch))
+                    };
+                }
+                Stmt::If {
+                    condition,
+                    then_branch: Box::new(self.nested(*then_branch)),
+                    else_branch: else_branch.map(|else_branch| Box::new(self.nested(*else_branch))),
+                }
+            }
+            Stmt::Print { expressions } => Stmt::Print { expressions: self.exprs(expressions) },
+            Stmt::Return { keyword, value } => Stmt::Return {
+                keyword,
+                value: value.map(|value| self.expr(value)),
+            },
+            Stmt::Var { name, initializer } => {
+                // not yet a constant while its own initializer is optimized
+                self.declare(&name.lexeme, None);
+                let initializer = initializer.map(|initializer| self.expr(initializer));
+                if let Some(Expr::Literal { value }) = &initializer {
+                    if !self.unstable.contains(&name.lexeme) {
+                        self.declare(&name.lexeme, Some(value.clone()));
+                    }
+                }
+                Stmt::Var { name, initializer }
+            }
+            Stmt::While { condition, body, increment } => Stmt::While {
+                condition: self.expr(condition),
+                body: Box::new(self.nested(*body)),
+                increment: increment.map(|increment| self.expr(increment)),
+            },
+            Stmt::Test { name, body } => Stmt::Test { name, body: self.block(body) },
+            Stmt::Assert { keyword, condition } => Stmt::Assert {
+                keyword,
+                condition: self.expr(condition),
+            },
+            Stmt::AssertEq { keyword, left, right } => Stmt::AssertEq {
+                keyword,
+                left: self.expr(left),
+                right: self.expr(right),
+            },
+            Stmt::Import { keyword, function_name, package_name } => {
+                self.declare(&function_name, None);
+                Stmt::Import { keyword, function_name, package_name }
+            }
+            stmt @ (Stmt::Break { .. } | Stmt::Continue { .. }) => stmt,
+        })
+    }
+
+    fn exprs(&mut self, exprs: Vec<Expr>) -> Vec<Expr> {
+        exprs.into_iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    /// Optimizes an expression in place, reusing its allocation.
+    fn boxed(&mut self, mut expr: Box<Expr>) -> Box<Expr> {
+        *expr = self.expr(*expr);
+        expr
+    }
+
+    fn expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Assign { name, value, depth } => Expr::Assign {
+                name,
+                value: self.boxed(value),
+                depth,
+            },
+            Expr::Binary { left, operator, right } => {
+                let left = self.expr(*left);
+                let right = self.expr(*right);
+                if let (Expr::Literal { value: l }, Expr::Literal { value: r }) = (&left, &right) {
+                    if let Ok(value) = operations::binary(&operator, l, r) {
+                        return Expr::Literal { value };
+                    }
+                }
+                Expr::Binary {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                }
+            }
+            Expr::Call { callee, paren, arguments } => Expr::Call {
+                callee: self.boxed(callee),
+                paren,
+                arguments: self.exprs(arguments),
+            },
+            Expr::CompoundSet { object, name, operator, value } => Expr::CompoundSet {
+                object: self.boxed(object),
+                name,
+                operator,
+                value: self.boxed(value),
+            },
+            Expr::CompoundIndexSet { object, bracket, index, operator, value } => {
+                Expr::CompoundIndexSet {
+                    object: self.boxed(object),
+                    bracket,
+                    index: self.boxed(index),
+                    operator,
+                    value: self.boxed(value),
+                }
+            }
+            Expr::Get { object, name } => Expr::Get { object: self.boxed(object), name },
+            Expr::Grouping { expression } => match self.expr(*expression) {
+                literal @ Expr::Literal { .. } => literal,
+                expression => Expr::Grouping { expression: Box::new(expression) },
+            },
+            Expr::Index { object, bracket, index } => Expr::Index {
+                object: self.boxed(object),
+                bracket,
+                index: self.boxed(index),
+            },
+            Expr::IndexSet { object, bracket, index, value } => Expr::IndexSet {
+                object: self.boxed(object),
+                bracket,
+                index: self.boxed(index),
+                value: self.boxed(value),
+            },
+            Expr::Interpolation { parts } => Expr::Interpolation { parts: self.exprs(parts) },
+            Expr::List { elements } => Expr::List { elements: self.exprs(elements) },
+            Expr::Map { brace, entries } => Expr::Map {
+                brace,
+                entries: entries
+                    .into_iter()
+                    .map(|(key, value)| (self.expr(key), self.expr(value)))
+                    .collect(),
+            },
+            Expr::Logical { left, operator, right } => Expr::Logical {
+                left: self.boxed(left),
+                operator,
+                right: self.boxed(right),
+            },
+            Expr::Set { object, name, value } => Expr::Set {
+                object: self.boxed(object),
+                name,
+                value: self.boxed(value),
+            },
+            Expr::Slice { object, bracket, start, end } => Expr::Slice {
+                object: self.boxed(object),
+                bracket,
+                start: start.map(|start| self.boxed(start)),
+                end: end.map(|end| self.boxed(end)),
+            },
+            Expr::Unary { operator, right } => {
+                let right = self.expr(*right);
+                if let Expr::Literal { value } = &right {
+                    if let Ok(value) = operations::unary(&operator, value) {
+                        return Expr::Literal { value };
+                    }
+                }
+                Expr::Unary { operator, right: Box::new(right) }
+            }
+            Expr::Variable { name, depth } => match self.constant(&name.lexeme) {
+                Some(value) => Expr::Literal { value },
+                None => Expr::Variable { name, depth },
+            },
+            expr @ (Expr::Literal { .. } | Expr::Super { .. } | Expr::This { .. }) => expr,
+        }
+    }
+}
+
+/// Adds every name assigned to anywhere in `stmt` to `names`.
+fn assignments_in_stmt(stmt: &Stmt, names: &mut HashSet<String>) {
+    let (statements, exprs): (Vec<&Stmt>, Vec<&Expr>) = match stmt {
+        Stmt::Block { statements }
+        | Stmt::Function { body: statements, .. }
+        | Stmt::Test { body: statements, .. }
+        | Stmt::Class { methods: statements, .. } => (statements.iter().collect(), Vec::new()),
+        Stmt::Expression { expression } => (Vec::new(), vec![expression]),
+        Stmt::If { condition, then_branch, else_branch } => (
+            std::iter::once(then_branch.as_ref()).chain(else_branch.as_deref()).collect(),
+            vec![condition],
+        ),
+        Stmt::Print { expressions } => (Vec::new(), expressions.iter().collect()),
+        Stmt::Return { value, .. } | Stmt::Var { initializer: value, .. } => {
+            (Vec::new(), value.iter().collect())
+        }
+        Stmt::While { condition, body, increment } => {
+            (vec![body], std::iter::once(condition).chain(increment).collect())
+        }
+        Stmt::Assert { condition, .. } => (Vec::new(), vec![condition]),
+        Stmt::AssertEq { left, right, .. } => (Vec::new(), vec![left, right]),
+        Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Import { .. } => (Vec::new(), Vec::new()),
+    };
+    for statement in statements {
+        assignments_in_stmt(statement, names);
+    }
+    for expr in exprs {
+        assignments_in_expr(expr, names);
+    }
+}
+
+fn assignments_in_expr(expr: &Expr, names: &mut HashSet<String>) {
+    let children: Vec<&Expr> = match expr {
+        Expr::Assign { name, value, .. } => {
+            names.insert(name.lexeme.clone());
+            vec![value]
+        }
+        Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => vec![left, right],
+        Expr::Call { callee, arguments, .. } => {
+            std::iter::once(callee.as_ref()).chain(arguments).collect()
+        }
+        Expr::Get { object, .. } => vec![object],
+        Expr::Grouping { expression } => vec![expression],
+        Expr::Index { object, index, .. } => vec![object, index],
+        Expr::IndexSet { object, index, value, .. }
+        | Expr::CompoundIndexSet { object, index, value, .. } => vec![object, index, value],
+        Expr::Interpolation { parts } => parts.iter().collect(),
+        Expr::List { elements } => elements.iter().collect(),
+        Expr::Map { entries, .. } => entries.iter().flat_map(|(key, value)| [key, value]).collect(),
+        Expr::Set { object, value, .. } | Expr::CompoundSet { object, value, .. } => {
+            vec![object, value]
+        }
+        Expr::Slice { object, start, end, .. } => std::iter::once(object.as_ref())
+            .chain(start.as_deref())
+            .chain(end.as_deref())
+            .collect(),
+        Expr::Unary { right, .. } => vec![right],
+        Expr::Literal { .. } | Expr::Super { .. } | Expr::This { .. } | Expr::Variable { .. } => {
+            Vec::new()
+        }
+    };
+    for child in children {
+        assignments_in_expr(child, names);
+    }
+}
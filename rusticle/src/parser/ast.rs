@@ -1,26 +1,84 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::interpreter::callable::Callable;
 use crate::interpreter::environment::Environment;
-use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::interpreter::{Interpreter, Outcome, Unwind};
+use crate::utils::error::RuntimeError;
 use crate::utils::token::Token;
 use crate::interpreter::visitor::Visitor;
+use crate::vm::chunk::Closure;
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 pub enum Object {
-    Number(f64),
+    Int(i64),
+    /// Named `Number` in package locks, which predate `Int`.
+    #[serde(rename = "Number")]
+    Float(f64),
     String(String),
     Bool(bool),
     Nil,
-    Function(Function),
+    Function(Rc<Function>),
+    #[serde(skip)]
+    Class(Rc<Class>),
+    #[serde(skip)]
+    Instance(Rc<RefCell<Instance>>),
+    #[serde(skip)]
+    Native(Rc<Native>),
+    /// A function compiled for the bytecode VM.
+    #[serde(skip)]
+    Closure(Rc<Closure>),
+    /// Shared, so every variable holding the list sees changes made
+    /// through any of them.
+    #[serde(skip)]
+    List(Rc<RefCell<Vec<Object>>>),
+    /// Shared like a list, and iterated in insertion order.
+    #[serde(skip)]
+    Map(Rc<RefCell<IndexMap<Key, Object>>>),
 }
 
-impl Object {
-    pub fn as_number(&self) -> f64 {
-        if let Object::Number(n) = self {
-            *n
-        } else {
-            panic!("Expected a number");
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(other, &mut Vec::new())
+    }
+}
+
+/// The integer a float is exactly equal to, if there is one. The bounds are
+/// checked first because `as` saturates, which would make `2.0 ** 63` equal
+/// to the largest integer.
+fn as_exact_int(n: f64) -> Option<i64> {
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0; // 2^63
+    if n.fract() == 0.0 && (-LIMIT..LIMIT).contains(&n) {
+        Some(n as i64)
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Object::Int(n) => write!(f, "{}", n),
+            // keeps the `.0` on whole floats so they can't pass for integers
+            Object::Float(n) => write!(f, "{:?}", n),
+            Object::String(s) => f.write_str(s),
+            Object::Bool(b) => write!(f, "{}", b),
+            Object::Nil => f.write_str("nil"),
+            Object::Function(function) => write!(f, "<functio {}>", function.name.lexeme),
+            Object::Class(class) => f.write_str(&class.name),
+            Object::Instance(instance) => write!(f, "{} instance", instance.borrow().class.name),
+            Object::Native(native) => write!(f, "<native functio {}>", native.name),
+            Object::Closure(closure) => write!(f, "<functio {}>", closure.function.name),
+            Object::List(_) | Object::Map(_) => self.fmt_collection(f, &mut Vec::new()),
         }
     }
+}
 
+impl Object {
     pub fn as_bool(&self) -> bool {
         match self {
             Object::Bool(b) => *b,
@@ -29,6 +87,148 @@ impl Object {
         }
     }
 
+    pub fn as_callable(&self) -> Option<&dyn Callable> {
+        match self {
+            Object::Function(function) => Some(function.as_ref()),
+            Object::Native(native) => Some(native.as_ref()),
+            Object::Class(class) => Some(class),
+            _ => None,
+        }
+    }
+
+    /// `==`. `comparing` holds the pairs of lists and maps already compared
+    /// further out: meeting one again means the two contain themselves in
+    /// the same place, which can't make them differ, so it counts as equal
+    /// instead of being compared forever.
+    fn equals(&self, other: &Self, comparing: &mut Vec<(*const (), *const ())>) -> bool {
+        match (self, other) {
+            (Object::Int(l), Object::Int(r)) => l == r,
+            (Object::Float(l), Object::Float(r)) => l == r,
+            // an integer equals the float with exactly the same value
+            (Object::Int(i), Object::Float(n)) | (Object::Float(n), Object::Int(i)) => {
+                as_exact_int(*n) == Some(*i)
+            }
+            (Object::String(l), Object::String(r)) => l == r,
+            (Object::Bool(l), Object::Bool(r)) => l == r,
+            (Object::Nil, Object::Nil) => true,
+            (Object::Function(l), Object::Function(r)) => l == r,
+            (Object::Class(l), Object::Class(r)) => l == r,
+            (Object::Instance(l), Object::Instance(r)) => l == r,
+            (Object::Native(l), Object::Native(r)) => l == r,
+            (Object::Closure(l), Object::Closure(r)) => Rc::ptr_eq(l, r),
+            (Object::List(l), Object::List(r)) => {
+                let pair = (Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ());
+                if Rc::ptr_eq(l, r) || comparing.contains(&pair) {
+                    return true;
+                }
+                let (l, r) = (l.borrow(), r.borrow());
+                if l.len() != r.len() {
+                    return false;
+                }
+                comparing.push(pair);
+                let equal = l.iter().zip(r.iter()).all(|(l, r)| l.equals(r, comparing));
+                comparing.pop();
+                equal
+            }
+            (Object::Map(l), Object::Map(r)) => {
+                let pair = (Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ());
+                if Rc::ptr_eq(l, r) || comparing.contains(&pair) {
+                    return true;
+                }
+                let (l, r) = (l.borrow(), r.borrow());
+                if l.len() != r.len() {
+                    return false;
+                }
+                // entries match by key, whatever order they were inserted in
+                comparing.push(pair);
+                let equal = l.iter().all(|(key, l)| match r.get(key) {
+                    Some(r) => l.equals(r, comparing),
+                    None => false,
+                });
+                comparing.pop();
+                equal
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes a list or map. `open` holds the ones already being written
+    /// further out, so one that contains itself shows up as `[...]` or
+    /// `#{...}` rather than recursing until the stack runs out.
+    fn fmt_collection(&self, f: &mut fmt::Formatter<'_>, open: &mut Vec<*const ()>) -> fmt::Result {
+        match self {
+            Object::List(list) => {
+                let id = Rc::as_ptr(list) as *const ();
+                if open.contains(&id) {
+                    return f.write_str("[...]");
+                }
+                open.push(id);
+                f.write_str("[")?;
+                for (i, element) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    element.fmt_nested(f, open)?;
+                }
+                open.pop();
+                f.write_str("]")
+            }
+            Object::Map(map) => {
+                let id = Rc::as_ptr(map) as *const ();
+                if open.contains(&id) {
+                    return f.write_str("#{...}");
+                }
+                open.push(id);
+                f.write_str("#{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", key)?;
+                    f.write_str(": ")?;
+                    value.fmt_nested(f, open)?;
+                }
+                open.pop();
+                f.write_str("}")
+            }
+            other => write!(f, "{}", other),
+        }
+    }
+
+    /// Writes a value inside a list or map, quoting strings so `["a, b"]`
+    /// can't be mistaken for two elements.
+    fn fmt_nested(&self, f: &mut fmt::Formatter<'_>, open: &mut Vec<*const ()>) -> fmt::Result {
+        match self {
+            Object::String(s) => write!(f, "{:?}", s),
+            other => other.fmt_collection(f, open),
+        }
+    }
+
+    /// The value as it is shown inside a list, so the string `"1"` and the
+    /// number `1` read differently.
+    pub fn repr(&self) -> String {
+        match self {
+            Object::String(s) => format!("{:?}", s),
+            other => other.to_string(),
+        }
+    }
+
+    /// Name of the value's type, as reported by `type()`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Int(_) => "int",
+            Object::Float(_) => "float",
+            Object::String(_) => "string",
+            Object::Bool(_) => "bool",
+            Object::Nil => "nil",
+            Object::Function(_) | Object::Native(_) | Object::Closure(_) => "function",
+            Object::Class(_) => "class",
+            Object::Instance(_) => "instance",
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+        }
+    }
+
     // pub fn as_string(&self) -> String {
     //     if let Object::String(s) = self {
     //         s.clone()
@@ -38,41 +238,355 @@ impl Object {
     // }
 }
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[derive(Clone, serde::Deserialize)]
 pub struct Function {
     pub name: Token,
-    params: Vec<Token>,
-    body: Vec<Stmt>,
+    pub params: Vec<Token>,
+    pub body: Rc<Vec<Stmt>>,
+    /// Scope the function was declared in. Packaged functions are loaded
+    /// without one and close over the globals instead.
+    #[serde(skip)]
+    closure: Option<Rc<RefCell<Environment>>>,
+    /// Set for a class's `init` method, which always hands back `this`.
+    #[serde(skip)]
+    is_initializer: bool,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the closure can contain this very function, so it is left out
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        let same_closure = match (&self.closure, &other.closure) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_closure
+            && self.name == other.name
+            && self.params == other.params
+            && self.body == other.body
+    }
 }
 
 impl Function {
-    pub fn new(name: Token, params: Vec<Token>, body: Vec<Stmt>) -> Self {
-        Function { name, params, body }
+    pub fn new(
+        name: Token,
+        params: Vec<Token>,
+        body: Rc<Vec<Stmt>>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
+        Function {
+            name,
+            params,
+            body,
+            closure: Some(closure),
+            is_initializer,
+        }
+    }
+
+    /// Returns a copy of this method whose scope has `this` bound to `instance`.
+    pub fn bind(&self, instance: Object, interpreter: &Interpreter) -> Function {
+        let closure = self.closure.clone().unwrap_or_else(|| interpreter.globals.clone());
+        let mut environment = Environment::new_enclosing(closure);
+        environment.define("this".to_string(), instance);
+        Function {
+            closure: Some(Rc::new(RefCell::new(environment))),
+            ..self.clone()
+        }
     }
 
-    pub fn arity(&self) -> usize {
-        self.params.len()
+}
+
+impl Callable for Function {
+    fn arity(&self) -> Option<usize> {
+        Some(self.params.len())
     }
 
-    pub fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> Object {
-        let mut environment = Environment::new();
+    fn call(&self, interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Outcome {
+        // the body sees its defining scope but none of the caller's locals
+        let closure = self.closure.clone().unwrap_or_else(|| interpreter.globals.clone());
+        let mut environment = Environment::new_enclosing(closure);
         for (param, arg) in self.params.iter().zip(arguments.iter()) {
             environment.define(param.lexeme.clone(), arg.clone());
         }
-        interpreter.execute_block(&self.body, environment)
+
+        // a `dede` anywhere in the body unwinds to here with its value
+        let value = match interpreter.execute_call(paren, &self.body, environment) {
+            Ok(_) => Object::Nil,
+            Err(Unwind::Return { value, .. }) => value,
+            Err(error) => return Err(error),
+        };
+
+        if self.is_initializer {
+            if let Some(closure) = &self.closure {
+                return Ok(closure.borrow().get_at(0, "this").unwrap_or(Object::Nil));
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// A map key. Only values with a well-defined equality can be keys, and
+/// whole floats are stored as integers so that `1`, `1.0` and `-0.0`
+/// behave as the keys they equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Bool(bool),
+    Int(i64),
+    /// The bits of a float with a fractional part.
+    Float(u64),
+    String(String),
+}
+
+impl Key {
+    pub fn from_object(value: &Object) -> Result<Key, String> {
+        match value {
+            Object::Bool(b) => Ok(Key::Bool(*b)),
+            Object::Int(n) => Ok(Key::Int(*n)),
+            Object::Float(n) if n.is_nan() => Err("NaN can't be used as a map key.".to_string()),
+            Object::Float(n) => match as_exact_int(*n) {
+                Some(i) => Ok(Key::Int(i)),
+                None => Ok(Key::Float(n.to_bits())),
+            },
+            Object::String(s) => Ok(Key::String(s.clone())),
+            other => Err(format!(
+                "Map keys must be numbers, strings or booleans, but got {}.",
+                other.type_name()
+            )),
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            Key::Bool(b) => Object::Bool(*b),
+            Key::Int(n) => Object::Int(*n),
+            Key::Float(bits) => Object::Float(f64::from_bits(*bits)),
+            Key::String(s) => Object::String(s.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    /// Shows the key as it would be written in a program.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_object().fmt_nested(f, &mut Vec::new())
+    }
+}
+
+pub type NativeFn = dyn Fn(&mut Interpreter, &[Object]) -> Result<Object, String>;
+
+/// A function implemented in Rust. Errors are plain messages; the
+/// interpreter attaches them to the call that failed.
+pub struct Native {
+    pub name: String,
+    /// `None` for functions taking any number of arguments, which then
+    /// check the count themselves.
+    pub arity: Option<usize>,
+    pub function: Rc<NativeFn>,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native functio {}>", self.name)
+    }
+}
+
+impl PartialEq for Native {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl Native {
+    pub fn new(
+        name: &str,
+        arity: Option<usize>,
+        function: impl Fn(&mut Interpreter, &[Object]) -> Result<Object, String> + 'static,
+    ) -> Self {
+        Native {
+            name: name.to_string(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+}
+
+impl Callable for Native {
+    fn arity(&self) -> Option<usize> {
+        self.arity
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Outcome {
+        (self.function)(interpreter, &arguments).map_err(|message| RuntimeError::new(paren, &message).into())
     }
 }
 
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    superclass: Option<Rc<Class>>,
+    methods: HashMap<String, Rc<Function>>,
+    /// Methods of a class built by the bytecode VM, which leaves `methods`
+    /// empty.
+    compiled: HashMap<String, Rc<Closure>>,
+}
+
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Class {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<Class>>,
+        methods: HashMap<String, Rc<Function>>,
+    ) -> Self {
+        Class {
+            name,
+            superclass,
+            methods,
+            compiled: HashMap::new(),
+        }
+    }
+
+    pub fn compiled(
+        name: String,
+        superclass: Option<Rc<Class>>,
+        compiled: HashMap<String, Rc<Closure>>,
+    ) -> Self {
+        Class {
+            name,
+            superclass,
+            methods: HashMap::new(),
+            compiled,
+        }
+    }
+
+    /// Looks the method up on this class and then along the superclass chain.
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+        match self.methods.get(name) {
+            Some(method) => Some(method.clone()),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+
+    /// Like `find_method`, for classes built by the bytecode VM.
+    pub fn find_compiled(&self, name: &str) -> Option<Rc<Closure>> {
+        match self.compiled.get(name) {
+            Some(method) => Some(method.clone()),
+            None => self.superclass.as_ref()?.find_compiled(name),
+        }
+    }
+
+}
+
+impl Callable for Rc<Class> {
+    /// A class takes the same arguments as its `init` method.
+    fn arity(&self) -> Option<usize> {
+        Some(self.find_method("init").map_or(0, |init| init.params.len()))
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Object>) -> Outcome {
+        let instance = Object::Instance(Rc::new(RefCell::new(Instance::new(self.clone()))));
+        if let Some(initializer) = self.find_method("init") {
+            initializer.bind(instance.clone(), interpreter).call(interpreter, paren, arguments)?;
+        }
+        Ok(instance)
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    fields: HashMap<String, Object>,
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // fields may point back at this instance, so only the class is shown
+        write!(f, "{} instance", self.class.name)
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+        Instance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Looks up a field first and then a method, binding the method to
+    /// `instance` so it can use `this`.
+    pub fn get(
+        instance: &Rc<RefCell<Instance>>,
+        name: &Token,
+        interpreter: &Interpreter,
+    ) -> Option<Object> {
+        let this = instance.borrow();
+        if let Some(value) = this.fields.get(&name.lexeme) {
+            return Some(value.clone());
+        }
+        let method = this.class.find_method(&name.lexeme)?;
+        let bound = method.bind(Object::Instance(instance.clone()), interpreter);
+        Some(Object::Function(Rc::new(bound)))
+    }
+
+    pub fn field(&self, name: &str) -> Option<Object> {
+        self.fields.get(name).cloned()
+    }
+
+    pub fn set(&mut self, name: &Token, value: Object) {
+        self.fields.insert(name.lexeme.clone(), value);
+    }
+}
+
+/// Number of scopes between a variable reference and its declaration,
+/// filled in by the resolver. `None` means the variable is global.
+pub type Depth = Cell<Option<usize>>;
+
 #[derive(Debug, Clone, PartialEq, serde::Deserialize)]
 pub enum Expr {
-    Assign { name: Token, value: Box<Expr> },
+    Assign { name: Token, value: Box<Expr>, #[serde(default)] depth: Depth },
     Binary { left: Box<Expr>, operator: Token, right: Box<Expr> },
     Call { callee: Box<Expr>, paren: Token, arguments: Vec<Expr> },
+    /// `object.name op= value`, evaluating `object` once. `operator` is the
+    /// binary operator applied, placed where the compound token was.
+    CompoundSet { object: Box<Expr>, name: Token, operator: Token, value: Box<Expr> },
+    /// `object[index] op= value`, evaluating `object` and `index` once.
+    CompoundIndexSet { object: Box<Expr>, bracket: Token, index: Box<Expr>, operator: Token, value: Box<Expr> },
+    Get { object: Box<Expr>, name: Token },
     Grouping { expression: Box<Expr> },
+    Index { object: Box<Expr>, bracket: Token, index: Box<Expr> },
+    IndexSet { object: Box<Expr>, bracket: Token, index: Box<Expr>, value: Box<Expr> },
+    Interpolation { parts: Vec<Expr> },
+    List { elements: Vec<Expr> },
+    Map { brace: Token, entries: Vec<(Expr, Expr)> },
     Literal { value: Object },
     Logical { left: Box<Expr>, operator: Token, right: Box<Expr> },
+    Set { object: Box<Expr>, name: Token, value: Box<Expr> },
+    Slice { object: Box<Expr>, bracket: Token, start: Option<Box<Expr>>, end: Option<Box<Expr>> },
+    Super { keyword: Token, method: Token, #[serde(default)] depth: Depth },
+    This { keyword: Token, #[serde(default)] depth: Depth },
     Unary { operator: Token, right: Box<Expr> },
-    Variable { name: Token },
+    Variable { name: Token, #[serde(default)] depth: Depth },
 }
 
 impl Expr {
@@ -81,9 +595,21 @@ impl Expr {
             Expr::Assign { .. } => visitor.visit_assign_expr(self),
             Expr::Binary { .. } => visitor.visit_binary_expr(self),
             Expr::Call { .. } => visitor.visit_call_expr(self),
+            Expr::CompoundSet { .. } => visitor.visit_compound_set_expr(self),
+            Expr::CompoundIndexSet { .. } => visitor.visit_compound_index_set_expr(self),
+            Expr::Get { .. } => visitor.visit_get_expr(self),
             Expr::Grouping { .. } => visitor.visit_grouping_expr(self),
+            Expr::Index { .. } => visitor.visit_index_expr(self),
+            Expr::IndexSet { .. } => visitor.visit_index_set_expr(self),
+            Expr::Interpolation { .. } => visitor.visit_interpolation_expr(self),
+            Expr::List { .. } => visitor.visit_list_expr(self),
+            Expr::Map { .. } => visitor.visit_map_expr(self),
             Expr::Literal { .. } => visitor.visit_literal_expr(self),
             Expr::Logical { .. } => visitor.visit_logical_expr(self),
+            Expr::Set { .. } => visitor.visit_set_expr(self),
+            Expr::Slice { .. } => visitor.visit_slice_expr(self),
+            Expr::Super { .. } => visitor.visit_super_expr(self),
+            Expr::This { .. } => visitor.visit_this_expr(self),
             Expr::Unary { .. } => visitor.visit_unary_expr(self),
             Expr::Variable { .. } => visitor.visit_variable_expr(self),
         }
@@ -97,11 +623,39 @@ pub enum Stmt {
     Expression { expression: Expr },
     Function { name: Token, params: Vec<Token>, body: Vec<Stmt> },
     If { condition: Expr, then_branch: Box<Stmt>, else_branch: Option<Box<Stmt>> },
-    Print { expression: Expr },
+    Print {
+        #[serde(alias = "expression", deserialize_with = "one_or_many")]
+        expressions: Vec<Expr>,
+    },
     Return { keyword: Token, value: Option<Expr> },
     Var { name: Token, initializer: Option<Expr> },
-    While { condition: Expr, body: Box<Stmt> },
-    Import { function_name: String, package_name: String }, // Add Import variant
+    /// `increment` is the third clause of a desugared `for`, run after
+    /// every iteration including those cut short by `agla`.
+    While { condition: Expr, body: Box<Stmt>, #[serde(default)] increment: Option<Expr> },
+    Break { keyword: Token },
+    Continue { keyword: Token },
+    /// A `test "name" { ... }` block, skipped by a normal run and executed
+    /// on its own by `rusticle test`.
+    Test { name: Token, body: Vec<Stmt> },
+    Assert { keyword: Token, condition: Expr },
+    AssertEq { keyword: Token, left: Expr, right: Expr },
+    Import { keyword: Token, function_name: String, package_name: String }, // Add Import variant
+}
+
+/// Package locks written before `likh` took several values store a single
+/// `expression` instead of a list.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Expr>, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Box<Expr>),
+        Many(Vec<Expr>),
+    }
+
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        OneOrMany::One(expression) => vec![*expression],
+        OneOrMany::Many(expressions) => expressions,
+    })
 }
 
 impl Stmt {
@@ -116,6 +670,11 @@ impl Stmt {
             Stmt::Return { .. } => visitor.visit_return_stmt(self),
             Stmt::Var { .. } => visitor.visit_var_stmt(self),
             Stmt::While { .. } => visitor.visit_while_stmt(self),
+            Stmt::Break { .. } => visitor.visit_break_stmt(self),
+            Stmt::Continue { .. } => visitor.visit_continue_stmt(self),
+            Stmt::Test { .. } => visitor.visit_test_stmt(self),
+            Stmt::Assert { .. } => visitor.visit_assert_stmt(self),
+            Stmt::AssertEq { .. } => visitor.visit_assert_eq_stmt(self),
             Stmt::Import { .. } => visitor.visit_import_stmt(self), // Add Import variant
         }
     }
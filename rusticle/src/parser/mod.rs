@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod parser;
 pub mod print_ast;
 pub mod ast;
\ No newline at end of file
@@ -1,29 +1,73 @@
 use crate::parser::ast::{Expr, Stmt, Object};
+use crate::utils::error::ParseError;
 use crate::utils::token::{Token, TokenType};
 use std::vec::Vec;
 
+type ParseResult<T> = Result<T, ParseError>;
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParseError>,
+    /// Number of loops around the statement being parsed, so `ruk` and
+    /// `agla` can be rejected outside of one.
+    loop_depth: usize,
 }
 
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser { tokens, current: 0, errors: Vec::new(), loop_depth: 0 }
     }
 
-    pub fn parse(&mut self) -> Vec<Stmt> {
+    /// Parses the whole program, returning every syntax error found if
+    /// there was at least one.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
         let mut statements: Vec<Stmt> = Vec::new();
         while !self.is_at_end() {
             if let Some(stmt) = self.declaration() {
                 statements.push(stmt);
             }
         }
-        statements
+
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Supplies the `;` missing after the last statement of a snippet, so
+    /// `1 + 2` can be evaluated without one. Input already ending in `;`
+    /// or `}` is left alone.
+    pub fn terminate(tokens: &mut Vec<Token>) {
+        let eof = tokens.len() - 1;
+        let ends_statement = eof == 0
+            || matches!(tokens[eof - 1].token_type, TokenType::Semicolon | TokenType::RightBrace);
+        if !ends_statement {
+            let semicolon = Token {
+                token_type: TokenType::Semicolon,
+                lexeme: ";".to_string(),
+                ..tokens[eof].clone()
+            };
+            tokens.insert(eof, semicolon);
+        }
     }
 
+    /// Parses a declaration. On a syntax error the error is recorded and
+    /// tokens are skipped up to the next statement so parsing can go on.
     fn declaration(&mut self) -> Option<Stmt> {
+        match self.try_declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(error) => {
+                self.errors.push(error);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn try_declaration(&mut self) -> ParseResult<Stmt> {
         if self.match_token(&[TokenType::Var]) {
             return self.var_declaration();
         }
@@ -36,20 +80,31 @@ impl Parser {
         if self.match_token(&[TokenType::Import]) {
             return self.import_statement();
         }
+        if self.match_token(&[TokenType::Test]) {
+            return self.test_declaration();
+        }
         self.statement()
     }
 
-    fn import_statement(&mut self) -> Option<Stmt> {
+    fn import_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
         self.consume(TokenType::String, "Expect function name.")?;
         let function_name = self.previous().lexeme.clone();
         self.consume(TokenType::From, "Expect 'from'.")?;
         self.consume(TokenType::String, "Expect package name.")?;
         let package_name = self.previous().lexeme.clone();
         self.consume(TokenType::Semicolon, "Expect ';' after import statement.")?;
-        Some(Stmt::Import { function_name, package_name })
+        Ok(Stmt::Import { keyword, function_name, package_name })
     }
 
-    fn var_declaration(&mut self) -> Option<Stmt> {
+    fn test_declaration(&mut self) -> ParseResult<Stmt> {
+        let name = self.consume(TokenType::String, "Expect test name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before test body.")?;
+        let body = self.block()?;
+        Ok(Stmt::Test { name, body })
+    }
+
+    fn var_declaration(&mut self) -> ParseResult<Stmt> {
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
         let initializer = if self.match_token(&[TokenType::Equal]) {
             Some(self.expression()?)
@@ -57,14 +112,17 @@ impl Parser {
             None
         };
         self.consume(TokenType::Semicolon, "Expect ';' after variable declaration.")?;
-        Some(Stmt::Var { name, initializer })
+        Ok(Stmt::Var { name, initializer })
     }
 
-    fn class_declaration(&mut self) -> Option<Stmt> {
+    fn class_declaration(&mut self) -> ParseResult<Stmt> {
         let name = self.consume(TokenType::Identifier, "Expect class name.")?;
         let superclass = if self.match_token(&[TokenType::Less]) {
             self.consume(TokenType::Identifier, "Expect superclass name.")?;
-            Some(Expr::Variable { name: self.previous().clone() })
+            Some(Expr::Variable {
+                name: self.previous().clone(),
+                depth: Default::default(),
+            })
         } else {
             None
         };
@@ -74,17 +132,18 @@ impl Parser {
             methods.push(self.function("method")?);
         }
         self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
-        Some(Stmt::Class { name, superclass, methods })
+        Ok(Stmt::Class { name, superclass, methods })
     }
 
-    fn function(&mut self, kind: &str) -> Option<Stmt> {
+    fn function(&mut self, kind: &str) -> ParseResult<Stmt> {
         let name = self.consume(TokenType::Identifier, &format!("Expect {} name.", kind))?;
         self.consume(TokenType::LeftParen, &format!("Expect '(' after {} name.", kind))?;
         let mut params = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
                 if params.len() >= 255 {
-                    self.error(self.peek(), "Can't have more than 255 parameters.");
+                    let error = self.error(self.peek(), "Can't have more than 255 parameters.");
+                    self.errors.push(error);
                 }
                 params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
                 if !self.match_token(&[TokenType::Comma]) {
@@ -94,19 +153,31 @@ impl Parser {
         }
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
         self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
-        let body = self.block()?;
-        Some(Stmt::Function { name, params, body })
+        // a loop around the declaration doesn't extend into the body
+        let enclosing_loops = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.block();
+        self.loop_depth = enclosing_loops;
+        Ok(Stmt::Function { name, params, body: body? })
     }
 
-    fn statement(&mut self) -> Option<Stmt> {
+    fn statement(&mut self) -> ParseResult<Stmt> {
         if self.match_token(&[TokenType::Print]) {
             return self.print_statement();
         }
         if self.match_token(&[TokenType::Return]) {
             return self.return_statement();
         }
+        if self.match_token(&[TokenType::Break, TokenType::Continue]) {
+            return self.loop_jump_statement();
+        }
+        if self.match_token(&[TokenType::Assert]) {
+            return self.assert_statement();
+        }
+        if self.match_token(&[TokenType::AssertEq]) {
+            return self.assert_eq_statement();
+        }
         if self.match_token(&[TokenType::LeftBrace]) {
-            return Some(Stmt::Block { statements: self.block()? });
+            return Ok(Stmt::Block { statements: self.block()? });
         }
         if self.match_token(&[TokenType::If]) {
             return self.if_statement();
@@ -120,13 +191,32 @@ impl Parser {
         self.expression_statement()
     }
 
-    fn print_statement(&mut self) -> Option<Stmt> {
-        let value = self.expression()?;
+    fn assert_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        let condition = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after assertion.")?;
+        Ok(Stmt::Assert { keyword, condition })
+    }
+
+    fn assert_eq_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        let left = self.expression()?;
+        self.consume(TokenType::Comma, "Expect ',' between the values to compare.")?;
+        let right = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after assertion.")?;
+        Ok(Stmt::AssertEq { keyword, left, right })
+    }
+
+    fn print_statement(&mut self) -> ParseResult<Stmt> {
+        let mut expressions = vec![self.expression()?];
+        while self.match_token(&[TokenType::Comma]) {
+            expressions.push(self.expression()?);
+        }
         self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
-        Some(Stmt::Print { expression: value })
+        Ok(Stmt::Print { expressions })
     }
 
-    fn return_statement(&mut self) -> Option<Stmt> {
+    fn return_statement(&mut self) -> ParseResult<Stmt> {
         let keyword = self.previous().clone();
         let value = if !self.check(TokenType::Semicolon) {
             Some(self.expression()?)
@@ -134,10 +224,26 @@ impl Parser {
             None
         };
         self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
-        Some(Stmt::Return { keyword, value })
+        Ok(Stmt::Return { keyword, value })
     }
 
-    fn if_statement(&mut self) -> Option<Stmt> {
+    /// Parses `ruk;` or `agla;`.
+    fn loop_jump_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous().clone();
+        if self.loop_depth == 0 {
+            // the parser isn't confused, so report without unwinding
+            let message = format!("Can't use '{}' outside of a loop.", keyword.lexeme);
+            let error = self.error(&keyword, &message);
+            self.errors.push(error);
+        }
+        self.consume(TokenType::Semicolon, &format!("Expect ';' after '{}'.", keyword.lexeme))?;
+        Ok(match keyword.token_type {
+            TokenType::Break => Stmt::Break { keyword },
+            _ => Stmt::Continue { keyword },
+        })
+    }
+
+    fn if_statement(&mut self) -> ParseResult<Stmt> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
@@ -147,18 +253,25 @@ impl Parser {
         } else {
             None
         };
-        Some(Stmt::If { condition, then_branch, else_branch })
+        Ok(Stmt::If { condition, then_branch, else_branch })
     }
 
-    fn while_statement(&mut self) -> Option<Stmt> {
+    fn while_statement(&mut self) -> ParseResult<Stmt> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
-        let body = Box::new(self.statement()?);
-        Some(Stmt::While { condition, body })
+        let body = Box::new(self.loop_body()?);
+        Ok(Stmt::While { condition, body, increment: None })
     }
 
-    fn for_statement(&mut self) -> Option<Stmt> {
+    fn loop_body(&mut self) -> ParseResult<Stmt> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn for_statement(&mut self) -> ParseResult<Stmt> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
         let initializer = if self.match_token(&[TokenType::Semicolon]) {
             None
@@ -179,28 +292,24 @@ impl Parser {
             None
         };
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
-        let mut body = Box::new(self.statement()?);
-        if let Some(increment) = increment {
-            body = Box::new(Stmt::Block {
-                statements: vec![*body, Stmt::Expression { expression: increment }],
-            });
-        }
-        body = Box::new(Stmt::While { condition, body });
+        let body = Box::new(self.loop_body()?);
+        // the increment stays outside the body so that `agla` still runs it
+        let mut body = Stmt::While { condition, body, increment };
         if let Some(initializer) = initializer {
-            body = Box::new(Stmt::Block {
-                statements: vec![initializer, *body],
-            });
+            body = Stmt::Block {
+                statements: vec![initializer, body],
+            };
         }
-        Some(*body)
+        Ok(body)
     }
 
-    fn expression_statement(&mut self) -> Option<Stmt> {
+    fn expression_statement(&mut self) -> ParseResult<Stmt> {
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
-        Some(Stmt::Expression { expression: expr })
+        Ok(Stmt::Expression { expression: expr })
     }
 
-    fn block(&mut self) -> Option<Vec<Stmt>> {
+    fn block(&mut self) -> ParseResult<Vec<Stmt>> {
         let mut statements = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
             if let Some(stmt) = self.declaration() {
@@ -208,34 +317,141 @@ impl Parser {
             }
         }
         self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
-        Some(statements)
-    }
-
-    fn expression(&mut self) -> Option<Expr> {
+        Ok(statements)
+    }
+
+    /// Operator precedence, from loosest to tightest binding. Every level
+    /// is left-associative except assignment and `**`.
+    ///
+    /// | Level | Operators | Method |
+    /// | --- | --- | --- |
+    /// | assignment | `=` `+=` `-=` `*=` `/=` `%=` | `assignment` |
+    /// | logical or | `or` | `or` |
+    /// | logical and | `and` | `and` |
+    /// | equality | `==` `!=` | `equality` |
+    /// | comparison | `<` `<=` `>` `>=` | `comparison` |
+    /// | bitwise or | `\|` | `bit_or` |
+    /// | bitwise xor | `^` | `bit_xor` |
+    /// | bitwise and | `&` | `bit_and` |
+    /// | shift | `<<` `>>` | `shift` |
+    /// | term | `+` `-` | `term` |
+    /// | factor | `*` `/` `~/` `%` | `factor` |
+    /// | unary | `!` `-` `~` | `unary` |
+    /// | power | `**` | `power` |
+    /// | call | `()` `.` `[]` | `call` |
+    fn expression(&mut self) -> ParseResult<Expr> {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> Option<Expr> {
-        let expr = self.or()?;
+    fn assignment(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.or()?;
 
-        if self.match_token(&[TokenType::Equal]) {
+        if self.match_token(&[
+            TokenType::Equal,
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::ModEqual,
+        ]) {
             let equals = self.previous().clone();
-            let value = self.assignment()?;
+            let mut value = self.assignment()?;
+
+            // a property or element target is evaluated once, so it gets a
+            // node of its own; `a op= b` is sugar for `a = a op b` otherwise
+            if let Some(operator) = Self::compound_operator(&equals) {
+                match expr {
+                    Expr::Get { object, name } => {
+                        return Ok(Expr::CompoundSet {
+                            object,
+                            name,
+                            operator,
+                            value: Box::new(value),
+                        });
+                    }
+                    Expr::Index { object, bracket, index } => {
+                        return Ok(Expr::CompoundIndexSet {
+                            object,
+                            bracket,
+                            index,
+                            operator,
+                            value: Box::new(value),
+                        });
+                    }
+                    target => {
+                        value = Expr::Binary {
+                            left: Box::new(Self::reread(&target)),
+                            operator,
+                            right: Box::new(value),
+                        };
+                        expr = target;
+                    }
+                }
+            }
 
-            if let Expr::Variable { name } = expr {
-                return Some(Expr::Assign {
+            if let Expr::Variable { name, .. } = expr {
+                return Ok(Expr::Assign {
+                    name,
+                    value: Box::new(value),
+                    depth: Default::default(),
+                });
+            }
+            if let Expr::Get { object, name } = expr {
+                return Ok(Expr::Set {
+                    object,
                     name,
                     value: Box::new(value),
                 });
             }
+            if let Expr::Index { object, bracket, index } = expr {
+                return Ok(Expr::IndexSet {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                });
+            }
 
-            self.error(&equals, "Invalid assignment target.");
+            // the parser isn't confused, so report without unwinding
+            let error = self.error(&equals, "Invalid assignment target.");
+            self.errors.push(error);
         }
 
-        Some(expr)
+        Ok(expr)
+    }
+
+    /// The binary operator a compound assignment applies, placed where the
+    /// compound token was so runtime errors point at it.
+    fn compound_operator(equals: &Token) -> Option<Token> {
+        let (token_type, lexeme) = match equals.token_type {
+            TokenType::PlusEqual => (TokenType::Plus, "+"),
+            TokenType::MinusEqual => (TokenType::Minus, "-"),
+            TokenType::StarEqual => (TokenType::Star, "*"),
+            TokenType::SlashEqual => (TokenType::Slash, "/"),
+            TokenType::ModEqual => (TokenType::Mod, "%"),
+            _ => return None,
+        };
+        Some(Token {
+            token_type,
+            lexeme: lexeme.to_string(),
+            ..equals.clone()
+        })
     }
 
-    fn or(&mut self) -> Option<Expr> {
+    /// Reads back a variable for the right-hand side of a compound
+    /// assignment. Anything that isn't a valid target is left for
+    /// `assignment` to report.
+    fn reread(target: &Expr) -> Expr {
+        match target {
+            Expr::Variable { name, .. } => Expr::Variable {
+                name: name.clone(),
+                depth: Default::default(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    fn or(&mut self) -> ParseResult<Expr> {
         let mut expr = self.and()?;
         while self.match_token(&[TokenType::Or]) {
             let operator = self.previous().clone();
@@ -246,10 +462,10 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn and(&mut self) -> Option<Expr> {
+    fn and(&mut self) -> ParseResult<Expr> {
         let mut expr = self.equality()?;
         while self.match_token(&[TokenType::And]) {
             let operator = self.previous().clone();
@@ -260,10 +476,10 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn equality(&mut self) -> Option<Expr> {
+    fn equality(&mut self) -> ParseResult<Expr> {
         let mut expr = self.comparison()?;
         while self.match_token(&[TokenType::BangEqual, TokenType::EqualEqual]) {
             let operator = self.previous().clone();
@@ -274,17 +490,73 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn comparison(&mut self) -> Option<Expr> {
-        let mut expr = self.term()?;
+    fn comparison(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_or()?;
         while self.match_token(&[
             TokenType::Greater,
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
+            let operator = self.previous().clone();
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn bit_or(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_xor()?;
+        while self.match_token(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_and()?;
+        while self.match_token(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.shift()?;
+        while self.match_token(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.term()?;
+        while self.match_token(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let operator = self.previous().clone();
             let right = self.term()?;
             expr = Expr::Binary {
@@ -293,10 +565,10 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Option<Expr> {
+    fn term(&mut self) -> ParseResult<Expr> {
         let mut expr = self.factor()?;
         while self.match_token(&[TokenType::Minus, TokenType::Plus]) {
             let operator = self.previous().clone();
@@ -307,12 +579,17 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn factor(&mut self) -> Option<Expr> {
+    fn factor(&mut self) -> ParseResult<Expr> {
         let mut expr = self.unary()?;
-        while self.match_token(&[TokenType::Slash, TokenType::Star]) {
+        while self.match_token(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::TildeSlash,
+            TokenType::Mod,
+        ]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
             expr = Expr::Binary {
@@ -321,35 +598,65 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn unary(&mut self) -> Option<Expr> {
-        if self.match_token(&[TokenType::Bang, TokenType::Minus]) {
+    fn unary(&mut self) -> ParseResult<Expr> {
+        if self.match_token(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
-            return Some(Expr::Unary {
+            return Ok(Expr::Unary {
                 operator,
                 right: Box::new(right),
             });
         }
-        self.call()
+        self.power()
     }
 
-    fn call(&mut self) -> Option<Expr> {
+    /// `**` binds tighter than a unary operator on its left, so `-2 ** 2`
+    /// is `-(2 ** 2)`, and recurses through `unary` on its right, which
+    /// makes it right-associative and allows `2 ** -1`.
+    fn power(&mut self) -> ParseResult<Expr> {
+        let expr = self.call()?;
+        if self.match_token(&[TokenType::StarStar]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> ParseResult<Expr> {
         let mut expr = self.primary()?;
-        while self.match_token(&[TokenType::LeftParen]) {
-            expr = self.finish_call(expr)?;
+        loop {
+            if self.match_token(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
+                let name = self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if self.match_token(&[TokenType::LeftBracket]) {
+                expr = self.finish_index(expr)?;
+            } else {
+                break;
+            }
         }
-        Some(expr)
+        Ok(expr)
     }
 
-    fn finish_call(&mut self, callee: Expr) -> Option<Expr> {
+    fn finish_call(&mut self, callee: Expr) -> ParseResult<Expr> {
         let mut arguments = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
                 if arguments.len() >= 255 {
-                    self.error(self.peek(), "Can't have more than 255 arguments.");
+                    let error = self.error(self.peek(), "Can't have more than 255 arguments.");
+                    self.errors.push(error);
                 }
                 arguments.push(self.expression()?);
                 if !self.match_token(&[TokenType::Comma]) {
@@ -358,50 +665,166 @@ impl Parser {
             }
         }
         let paren: Token = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
-        Some(Expr::Call {
+        Ok(Expr::Call {
             callee: Box::new(callee),
             paren,
             arguments,
         })
     }
 
-    fn primary(&mut self) -> Option<Expr> {
+    /// Parses `[index]` or a slice `[start:end]`, where either bound of
+    /// the slice may be left out.
+    fn finish_index(&mut self, object: Expr) -> ParseResult<Expr> {
+        let bracket = self.previous().clone();
+        let start = if self.check(TokenType::Colon) {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+
+        if self.match_token(&[TokenType::Colon]) {
+            let end = if self.check(TokenType::RightBracket) {
+                None
+            } else {
+                Some(Box::new(self.expression()?))
+            };
+            self.consume(TokenType::RightBracket, "Expect ']' after slice.")?;
+            return Ok(Expr::Slice {
+                object: Box::new(object),
+                bracket,
+                start,
+                end,
+            });
+        }
+
+        self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+        Ok(Expr::Index {
+            object: Box::new(object),
+            bracket,
+            index: start.expect("an index is parsed unless a ':' follows the '['"),
+        })
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
         if self.match_token(&[TokenType::False]) {
-            return Some(Expr::Literal {
+            return Ok(Expr::Literal {
                 value: Object::Bool(false),
             });
         }
         if self.match_token(&[TokenType::True]) {
-            return Some(Expr::Literal {
+            return Ok(Expr::Literal {
                 value: Object::Bool(true),
             });
         }
         if self.match_token(&[TokenType::Nil]) {
-            return Some(Expr::Literal { value: Object::Nil });
+            return Ok(Expr::Literal { value: Object::Nil });
         }
         if self.match_token(&[TokenType::Number]) {
-            return Some(Expr::Literal {
-                value: Object::Number(self.previous().lexeme.parse().ok()?),
-            });
+            // a literal is a float exactly when it is written with a `.`
+            let lexeme = &self.previous().lexeme;
+            let value = if lexeme.contains('.') {
+                lexeme.parse().map(Object::Float).ok()
+            } else {
+                lexeme.parse().map(Object::Int).ok()
+            };
+            return match value {
+                Some(value) => Ok(Expr::Literal { value }),
+                None if !lexeme.contains('.') => {
+                    Err(self.error(self.previous(), "Integer literal is too large."))
+                }
+                None => Err(self.error(self.previous(), "Invalid number literal.")),
+            };
         }
         if self.match_token(&[TokenType::String]) {
-            return Some(Expr::Literal {
+            return Ok(Expr::Literal {
                 value: Object::String(self.previous().lexeme.clone()),
             });
         }
+        if self.match_token(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+        if self.match_token(&[TokenType::Super]) {
+            let keyword = self.previous().clone();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+            return Ok(Expr::Super {
+                keyword,
+                method,
+                depth: Default::default(),
+            });
+        }
+        if self.match_token(&[TokenType::This]) {
+            return Ok(Expr::This {
+                keyword: self.previous().clone(),
+                depth: Default::default(),
+            });
+        }
         if self.match_token(&[TokenType::Identifier]) {
-            return Some(Expr::Variable {
+            return Ok(Expr::Variable {
                 name: self.previous().clone(),
+                depth: Default::default(),
             });
         }
+        if self.match_token(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            while !self.check(TokenType::RightBracket) {
+                elements.push(self.expression()?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List { elements });
+        }
+        if self.match_token(&[TokenType::HashBrace]) {
+            let brace = self.previous().clone();
+            let mut entries = Vec::new();
+            while !self.check(TokenType::RightBrace) {
+                let key = self.expression()?;
+                self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                entries.push((key, self.expression()?));
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map { brace, entries });
+        }
         if self.match_token(&[TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
-            return Some(Expr::Grouping {
+            return Ok(Expr::Grouping {
                 expression: Box::new(expr),
             });
         }
-        None
+        Err(self.error(self.peek(), "Expect expression."))
+    }
+
+    /// Collects the text and embedded expressions of an interpolated string.
+    /// The lexer emits an `Interpolation` token for each part ending in `${`
+    /// and an `InterpolationEnd` for the part after the last `}`.
+    fn interpolation(&mut self) -> ParseResult<Expr> {
+        let mut parts = Vec::new();
+        loop {
+            let text = self.previous().lexeme.clone();
+            if !text.is_empty() {
+                parts.push(Expr::Literal {
+                    value: Object::String(text),
+                });
+            }
+            parts.push(self.expression()?);
+
+            if self.match_token(&[TokenType::Interpolation]) {
+                continue;
+            }
+            let end = self.consume(TokenType::InterpolationEnd, "Expect '}' after interpolated expression.")?;
+            if !end.lexeme.is_empty() {
+                parts.push(Expr::Literal {
+                    value: Object::String(end.lexeme),
+                });
+            }
+            return Ok(Expr::Interpolation { parts });
+        }
     }
 
     fn match_token(&mut self, types: &[TokenType]) -> bool {
@@ -414,12 +837,11 @@ impl Parser {
         false
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Option<Token> {
+    fn consume(&mut self, token_type: TokenType, message: &str) -> ParseResult<Token> {
         if self.check(token_type) {
-            return Some(self.advance().clone());
+            return Ok(self.advance().clone());
         }
-        self.error(self.peek(), message);
-        None
+        Err(self.error(self.peek(), message))
     }
 
     fn check(&self, token_type: TokenType) -> bool {
@@ -448,7 +870,36 @@ impl Parser {
         &self.tokens[self.current - 1]
     }
 
-    fn error(&self, token: &Token, message: &str) {
-        eprintln!("[line {}] Error at '{}': {}", token.line, token.lexeme, message);
+    fn error(&self, token: &Token, message: &str) -> ParseError {
+        ParseError::new(token, message)
+    }
+
+    /// Discards tokens until just past a `;` or right before a keyword that
+    /// starts a new statement.
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::Semicolon {
+                return;
+            }
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Break
+                | TokenType::Continue
+                | TokenType::Test
+                | TokenType::Assert
+                | TokenType::AssertEq
+                | TokenType::Import => return,
+                _ => {}
+            }
+            self.advance();
+        }
     }
 }
\ No newline at end of file
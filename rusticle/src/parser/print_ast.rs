@@ -4,6 +4,12 @@ use crate::interpreter::visitor::Visitor;
 pub struct AstPrinter;
 
 
+impl Default for AstPrinter {
+    fn default() -> Self {
+        AstPrinter::new()
+    }
+}
+
 impl AstPrinter {
     pub fn new() -> Self {
         AstPrinter
@@ -18,7 +24,7 @@ impl AstPrinter {
 
 impl Visitor<String> for AstPrinter {
     fn visit_assign_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Assign { name, value } = expr {
+        if let Expr::Assign { name, value, .. } = expr {
             format!("(assign {} {})", name.lexeme, value.accept(self))
         } else {
             unreachable!()
@@ -26,7 +32,7 @@ impl Visitor<String> for AstPrinter {
     }
     
     fn visit_import_stmt(&mut self, stmt: &Stmt) -> String {
-        if let Stmt::Import { function_name, package_name } = stmt {
+        if let Stmt::Import { function_name, package_name, .. } = stmt {
             format!("(import \"{}\" from \"{}\")", function_name, package_name)
         } else {
             unreachable!()
@@ -50,6 +56,36 @@ impl Visitor<String> for AstPrinter {
         }
     }
 
+    fn visit_compound_set_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::CompoundSet { object, name, operator, value } = expr {
+            format!("(compound-set {} {} {} {})", operator.lexeme, object.accept(self), name.lexeme, value.accept(self))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_compound_index_set_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::CompoundIndexSet { object, index, operator, value, .. } = expr {
+            format!(
+                "(compound-index-set {} {} {} {})",
+                operator.lexeme,
+                object.accept(self),
+                index.accept(self),
+                value.accept(self)
+            )
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Get { object, name } = expr {
+            format!("(get {} {})", object.accept(self), name.lexeme)
+        } else {
+            unreachable!()
+        }
+    }
+
     fn visit_grouping_expr(&mut self, expr: &Expr) -> String {
         if let Expr::Grouping { expression } = expr {
             format!("(group {})", expression.accept(self))
@@ -58,6 +94,52 @@ impl Visitor<String> for AstPrinter {
         }
     }
 
+    fn visit_index_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Index { object, index, .. } = expr {
+            format!("(index {} {})", object.accept(self), index.accept(self))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::IndexSet { object, index, value, .. } = expr {
+            format!("(index-set {} {} {})", object.accept(self), index.accept(self), value.accept(self))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Interpolation { parts } = expr {
+            let parts: Vec<String> = parts.iter().map(|part| part.accept(self)).collect();
+            format!("(interpolate {})", parts.join(" "))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::List { elements } = expr {
+            let elements: Vec<String> = elements.iter().map(|element| element.accept(self)).collect();
+            format!("(list {})", elements.join(" "))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Map { entries, .. } = expr {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("({} {})", key.accept(self), value.accept(self)))
+                .collect();
+            format!("(map {})", entries.join(" "))
+        } else {
+            unreachable!()
+        }
+    }
+
     fn visit_literal_expr(&mut self, expr: &Expr) -> String {
         if let Expr::Literal { value } = expr {
             format!("{:?}", value)
@@ -74,6 +156,37 @@ impl Visitor<String> for AstPrinter {
         }
     }
 
+    fn visit_set_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Set { object, name, value } = expr {
+            format!("(set {} {} {})", object.accept(self), name.lexeme, value.accept(self))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_slice_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Slice { object, start, end, .. } = expr {
+            let mut bound = |bound: &Option<Box<Expr>>| bound.as_ref().map_or("_".to_string(), |b| b.accept(self));
+            let start = bound(start);
+            let end = bound(end);
+            format!("(slice {} {} {})", object.accept(self), start, end)
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_super_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Super { method, .. } = expr {
+            format!("(super {})", method.lexeme)
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_this_expr(&mut self, _expr: &Expr) -> String {
+        "this".to_string()
+    }
+
     fn visit_unary_expr(&mut self, expr: &Expr) -> String {
         if let Expr::Unary { operator, right } = expr {
             format!("({} {})", operator.lexeme, right.accept(self))
@@ -83,8 +196,8 @@ impl Visitor<String> for AstPrinter {
     }
 
     fn visit_variable_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Variable { name } = expr {
-            format!("{}", name.lexeme)
+        if let Expr::Variable { name, .. } = expr {
+            name.lexeme.clone()
         } else {
             unreachable!()
         }
@@ -145,8 +258,9 @@ impl Visitor<String> for AstPrinter {
     }
 
     fn visit_print_stmt(&mut self, stmt: &Stmt) -> String {
-        if let Stmt::Print { expression } = stmt {
-            format!("(print {})", expression.accept(self))
+        if let Stmt::Print { expressions } = stmt {
+            let values: Vec<String> = expressions.iter().map(|value| value.accept(self)).collect();
+            format!("(print {})", values.join(" "))
         } else {
             unreachable!()
         }
@@ -177,8 +291,49 @@ impl Visitor<String> for AstPrinter {
     }
 
     fn visit_while_stmt(&mut self, stmt: &Stmt) -> String {
-        if let Stmt::While { condition, body } = stmt {
-            format!("(while {} {})", condition.accept(self), body.accept(self))
+        if let Stmt::While { condition, body, increment } = stmt {
+            match increment {
+                Some(increment) => format!(
+                    "(while {} {} {})",
+                    condition.accept(self),
+                    body.accept(self),
+                    increment.accept(self)
+                ),
+                None => format!("(while {} {})", condition.accept(self), body.accept(self)),
+            }
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_break_stmt(&mut self, _stmt: &Stmt) -> String {
+        "(break)".to_string()
+    }
+
+    fn visit_continue_stmt(&mut self, _stmt: &Stmt) -> String {
+        "(continue)".to_string()
+    }
+
+    fn visit_test_stmt(&mut self, stmt: &Stmt) -> String {
+        if let Stmt::Test { name, body } = stmt {
+            let stmts: Vec<String> = body.iter().map(|stmt| stmt.accept(self)).collect();
+            format!("(test {:?} {})", name.lexeme, stmts.join(" "))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_assert_stmt(&mut self, stmt: &Stmt) -> String {
+        if let Stmt::Assert { condition, .. } = stmt {
+            format!("(assert {})", condition.accept(self))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_assert_eq_stmt(&mut self, stmt: &Stmt) -> String {
+        if let Stmt::AssertEq { left, right, .. } = stmt {
+            format!("(assert_eq {} {})", left.accept(self), right.accept(self))
         } else {
             unreachable!()
         }
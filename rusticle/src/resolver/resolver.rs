@@ -0,0 +1,484 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::interpreter::visitor::Visitor;
+use crate::parser::ast::{Depth, Expr, Function, Stmt};
+use crate::utils::error::ResolveError;
+use crate::utils::token::Token;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+/// Static pass between the parser and the interpreter. It records how many
+/// scopes separate every variable reference from its declaration and
+/// collects the errors that can be found without running the program.
+pub struct Resolver {
+    /// One map per local scope; the flag turns true once the declaration's
+    /// initializer has been resolved.
+    scopes: Vec<HashMap<String, bool>>,
+    globals: HashSet<String>,
+    /// Assignments to names that weren't local, checked against every
+    /// global once the whole program has been seen.
+    global_assignments: Vec<Token>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    errors: Vec<ResolveError>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            scopes: Vec::new(),
+            globals: HashSet::new(),
+            global_assignments: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Creates a resolver that treats `globals` as already declared, for
+    /// code run against an interpreter that has executed earlier input.
+    pub fn with_globals(globals: impl IntoIterator<Item = String>) -> Self {
+        Resolver {
+            globals: globals.into_iter().collect(),
+            ..Resolver::new()
+        }
+    }
+
+    pub fn resolve(mut self, statements: &[Stmt]) -> Result<(), Vec<ResolveError>> {
+        self.resolve_statements(statements);
+
+        // globals can be declared after a function that assigns them
+        for name in std::mem::take(&mut self.global_assignments) {
+            if !self.globals.contains(&name.lexeme) {
+                let message = format!("Undefined variable '{}'.", name.lexeme);
+                self.error(&name, &message);
+            }
+        }
+
+        self.finish()
+    }
+
+    /// Resolves a function loaded from a package lock, which never went
+    /// through the resolver together with the program importing it.
+    pub fn resolve_packaged(mut self, function: &Function) -> Result<(), Vec<ResolveError>> {
+        self.resolve_function(&function.params, &function.body, FunctionType::Function);
+        self.finish()
+    }
+
+    fn finish(mut self) -> Result<(), Vec<ResolveError>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            self.errors.sort_by_key(|error| error.token.line);
+            Err(self.errors)
+        }
+    }
+
+    fn resolve_statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            statement.accept(self);
+        }
+    }
+
+    fn resolve_function(&mut self, params: &[Token], body: &[Stmt], kind: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        // the body shares the parameters' scope, just like `Function::call`
+        self.resolve_statements(body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            self.globals.insert(name.lexeme.clone());
+            return;
+        };
+
+        if scope.contains_key(&name.lexeme) {
+            self.error(name, "Already a variable with this name in this scope.");
+            return;
+        }
+        scope.insert(name.lexeme.clone(), false);
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    /// Stores the distance to the innermost scope declaring `name`,
+    /// leaving `depth` empty when the name is global.
+    fn resolve_local(&mut self, name: &Token, depth: &Depth) -> bool {
+        for (distance, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                depth.set(Some(distance));
+                return true;
+            }
+        }
+        depth.set(None);
+        false
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.errors.push(ResolveError::new(token, message));
+    }
+}
+
+impl Visitor<()> for Resolver {
+    fn visit_assign_expr(&mut self, expr: &Expr) {
+        if let Expr::Assign { name, value, depth } = expr {
+            value.accept(self);
+            if !self.resolve_local(name, depth) {
+                self.global_assignments.push(name.clone());
+            }
+        }
+    }
+
+    fn visit_binary_expr(&mut self, expr: &Expr) {
+        if let Expr::Binary { left, right, .. } = expr {
+            left.accept(self);
+            right.accept(self);
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { callee, arguments, .. } = expr {
+            callee.accept(self);
+            for argument in arguments {
+                argument.accept(self);
+            }
+        }
+    }
+
+    fn visit_compound_set_expr(&mut self, expr: &Expr) {
+        if let Expr::CompoundSet { object, value, .. } = expr {
+            object.accept(self);
+            value.accept(self);
+        }
+    }
+
+    fn visit_compound_index_set_expr(&mut self, expr: &Expr) {
+        if let Expr::CompoundIndexSet { object, index, value, .. } = expr {
+            object.accept(self);
+            index.accept(self);
+            value.accept(self);
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) {
+        if let Expr::Get { object, .. } = expr {
+            object.accept(self);
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        if let Expr::Grouping { expression } = expr {
+            expression.accept(self);
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) {
+        if let Expr::Index { object, index, .. } = expr {
+            object.accept(self);
+            index.accept(self);
+        }
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) {
+        if let Expr::IndexSet { object, index, value, .. } = expr {
+            object.accept(self);
+            index.accept(self);
+            value.accept(self);
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) {
+        if let Expr::Interpolation { parts } = expr {
+            for part in parts {
+                part.accept(self);
+            }
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) {
+        if let Expr::List { elements } = expr {
+            for element in elements {
+                element.accept(self);
+            }
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) {
+        if let Expr::Map { entries, .. } = expr {
+            for (key, value) in entries {
+                key.accept(self);
+                value.accept(self);
+            }
+        }
+    }
+
+    fn visit_literal_expr(&mut self, _expr: &Expr) {}
+
+    fn visit_logical_expr(&mut self, expr: &Expr) {
+        if let Expr::Logical { left, right, .. } = expr {
+            left.accept(self);
+            right.accept(self);
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) {
+        if let Expr::Set { object, value, .. } = expr {
+            value.accept(self);
+            object.accept(self);
+        }
+    }
+
+    fn visit_slice_expr(&mut self, expr: &Expr) {
+        if let Expr::Slice { object, start, end, .. } = expr {
+            object.accept(self);
+            for bound in [start, end].into_iter().flatten() {
+                bound.accept(self);
+            }
+        }
+    }
+
+    fn visit_super_expr(&mut self, expr: &Expr) {
+        if let Expr::Super { keyword, depth, .. } = expr {
+            match self.current_class {
+                ClassType::None => {
+                    self.error(keyword, "Can't use 'super' outside of a class.");
+                }
+                ClassType::Class => {
+                    self.error(keyword, "Can't use 'super' in a class with no superclass.");
+                }
+                ClassType::Subclass => {
+                    self.resolve_local(keyword, depth);
+                }
+            }
+        }
+    }
+
+    fn visit_this_expr(&mut self, expr: &Expr) {
+        if let Expr::This { keyword, depth } = expr {
+            if self.current_class == ClassType::None {
+                self.error(keyword, "Can't use 'this' outside of a class.");
+                return;
+            }
+            self.resolve_local(keyword, depth);
+        }
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) {
+        if let Expr::Unary { right, .. } = expr {
+            right.accept(self);
+        }
+    }
+
+    fn visit_variable_expr(&mut self, expr: &Expr) {
+        if let Expr::Variable { name, depth } = expr {
+            let in_initializer = self
+                .scopes
+                .last()
+                .and_then(|scope| scope.get(&name.lexeme))
+                == Some(&false);
+            if in_initializer {
+                self.error(name, "Can't read local variable in its own initializer.");
+            }
+            self.resolve_local(name, depth);
+        }
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Block { statements } = stmt {
+            self.begin_scope();
+            self.resolve_statements(statements);
+            self.end_scope();
+        }
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Class { name, superclass, methods } = stmt {
+            let enclosing_class = self.current_class;
+            self.current_class = ClassType::Class;
+
+            self.declare(name);
+            self.define(name);
+
+            if let Some(superclass) = superclass {
+                if let Expr::Variable { name: superclass_name, .. } = superclass {
+                    if superclass_name.lexeme == name.lexeme {
+                        self.error(superclass_name, "A class can't inherit from itself.");
+                    }
+                }
+                self.current_class = ClassType::Subclass;
+                superclass.accept(self);
+
+                // the interpreter keeps `super` in a scope of its own
+                self.begin_scope();
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert("super".to_string(), true);
+                }
+            }
+
+            // matches the scope `Function::bind` creates around each method
+            self.begin_scope();
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert("this".to_string(), true);
+            }
+            for method in methods {
+                if let Stmt::Function { name, params, body } = method {
+                    let kind = if name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(params, body, kind);
+                }
+            }
+            self.end_scope();
+
+            if superclass.is_some() {
+                self.end_scope();
+            }
+
+            self.current_class = enclosing_class;
+        }
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Expression { expression } = stmt {
+            expression.accept(self);
+        }
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Function { name, params, body } = stmt {
+            // defined before the body so the function can call itself
+            self.declare(name);
+            self.define(name);
+            self.resolve_function(params, body, FunctionType::Function);
+        }
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::If { condition, then_branch, else_branch } = stmt {
+            condition.accept(self);
+            then_branch.accept(self);
+            if let Some(else_branch) = else_branch {
+                else_branch.accept(self);
+            }
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Print { expressions } = stmt {
+            for expression in expressions {
+                expression.accept(self);
+            }
+        }
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Return { keyword, value } = stmt {
+            if self.current_function == FunctionType::None {
+                self.error(keyword, "Can't return from top-level code.");
+            }
+            if let Some(value) = value {
+                if self.current_function == FunctionType::Initializer {
+                    self.error(keyword, "Can't return a value from an initializer.");
+                }
+                value.accept(self);
+            }
+        }
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Var { name, initializer } = stmt {
+            self.declare(name);
+            if let Some(initializer) = initializer {
+                initializer.accept(self);
+            }
+            self.define(name);
+        }
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::While { condition, body, increment } = stmt {
+            condition.accept(self);
+            body.accept(self);
+            if let Some(increment) = increment {
+                increment.accept(self);
+            }
+        }
+    }
+
+    fn visit_break_stmt(&mut self, _stmt: &Stmt) {}
+
+    fn visit_continue_stmt(&mut self, _stmt: &Stmt) {}
+
+    fn visit_test_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Test { name, body } = stmt {
+            if !self.scopes.is_empty() {
+                self.error(name, "Test blocks must be at the top level.");
+            }
+            self.begin_scope();
+            self.resolve_statements(body);
+            self.end_scope();
+        }
+    }
+
+    fn visit_assert_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Assert { condition, .. } = stmt {
+            condition.accept(self);
+        }
+    }
+
+    fn visit_assert_eq_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::AssertEq { left, right, .. } = stmt {
+            left.accept(self);
+            right.accept(self);
+        }
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Import { function_name, .. } = stmt {
+            self.globals.insert(function_name.clone());
+        }
+    }
+}
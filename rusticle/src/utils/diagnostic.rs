@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Location of a token or error in the source it came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    /// 1-based column, or 0 when the span isn't from a source file.
+    pub column: usize,
+    /// Byte offset of the first character.
+    pub offset: usize,
+    /// Length in bytes.
+    pub length: usize,
+}
+
+/// An error that can point at the exact place in the source it came from.
+/// Its `Display` output is the one-line summary shown above the snippet.
+pub trait Diagnostic: fmt::Display {
+    fn span(&self) -> Span;
+}
+
+/// Renders a diagnostic in the style of rustc, quoting the offending source
+/// line and underlining the span:
+///
+/// ```text
+/// [line 2] RuntimeError: Operands must be numbers.
+///  --> example.lin:2:6
+///   |
+/// 2 | likh 1 + "a";
+///   |      ^
+/// ```
+pub fn render(source: &str, path: &str, diagnostic: &dyn Diagnostic) -> String {
+    let summary = diagnostic.to_string();
+    let span = diagnostic.span();
+
+    let text = match span.line.checked_sub(1).and_then(|index| source.lines().nth(index)) {
+        Some(text) if span.column > 0 => text,
+        _ => return summary,
+    };
+
+    // a span running over several lines is only underlined on its first
+    let width = source
+        .get(span.offset..span.offset + span.length)
+        .and_then(|spanned| spanned.lines().next())
+        .map_or(0, |first| first.chars().count())
+        .max(1);
+
+    // keep tabs so the caret lines up with the quoted line
+    let padding: String = text
+        .chars()
+        .take(span.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let line_number = span.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+
+    format!(
+        "{summary}\n{gutter}--> {path}:{line}:{column}\n{gutter} |\n{line_number} | {text}\n{gutter} | {padding}^{underline}",
+        line = span.line,
+        column = span.column,
+        underline = "~".repeat(width - 1),
+    )
+}
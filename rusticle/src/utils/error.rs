@@ -0,0 +1,126 @@
+use std::fmt;
+
+use crate::utils::diagnostic::{Diagnostic, Span};
+use crate::utils::token::{Token, TokenType};
+
+/// An unexpected character or malformed literal found by the lexer.
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[line {}, column {}] Error: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+/// A static error found by the resolver before any code runs.
+#[derive(Debug, Clone)]
+pub struct ResolveError {
+    pub token: Token,
+    pub message: String,
+}
+
+impl ResolveError {
+    pub fn new(token: &Token, message: &str) -> Self {
+        ResolveError {
+            token: token.clone(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] Error at '{}': {}",
+            self.token.line, self.token.lexeme, self.message
+        )
+    }
+}
+
+/// An error raised while a program runs, pointing at the token that caused it.
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub token: Token,
+    pub message: String,
+}
+
+impl RuntimeError {
+    pub fn new(token: &Token, message: &str) -> Self {
+        RuntimeError {
+            token: token.clone(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[line {}] RuntimeError: {}", self.token.line, self.message)
+    }
+}
+
+/// A syntax error found by the parser.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub token: Token,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(token: &Token, message: &str) -> Self {
+        ParseError {
+            token: token.clone(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.token_type == TokenType::Eof {
+            write!(f, "[line {}] Error at end: {}", self.token.line, self.message)
+        } else if self.token.token_type == TokenType::InterpolationEnd {
+            // the token starts at the `}` but its lexeme is the text after it
+            write!(f, "[line {}] Error at '}}': {}", self.token.line, self.message)
+        } else {
+            write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                self.token.line, self.token.lexeme, self.message
+            )
+        }
+    }
+}
+
+impl Diagnostic for LexError {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Diagnostic for ParseError {
+    fn span(&self) -> Span {
+        self.token.span()
+    }
+}
+
+impl Diagnostic for ResolveError {
+    fn span(&self) -> Span {
+        self.token.span()
+    }
+}
+
+impl Diagnostic for RuntimeError {
+    fn span(&self) -> Span {
+        self.token.span()
+    }
+}
@@ -1 +1,3 @@
-pub mod token;
\ No newline at end of file
+pub mod token;
+pub mod error;
+pub mod diagnostic;
\ No newline at end of file
@@ -1,32 +1,70 @@
+use crate::utils::diagnostic::Span;
+
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
 pub enum TokenType {
     // Single-character tokens
-    LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star, Mod,
+    LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
+    // `#{`, which opens a map literal
+    HashBrace,
+    Comma, Dot, Colon, Minus, Plus, Semicolon, Slash, Star, Mod,
+    Ampersand, Pipe, Caret, Tilde,
     // One or two character tokens
     Bang, BangEqual, Equal, EqualEqual,
     Greater, GreaterEqual, Less, LessEqual,
+    StarStar, TildeSlash, LessLess, GreaterGreater,
+    PlusEqual, MinusEqual, StarEqual, SlashEqual, ModEqual,
     // Literals
     Identifier, String, Number,
+    // Part of a string that continues with `${ expression }`
+    Interpolation,
+    // The rest of a string after the `}` of its last interpolation
+    InterpolationEnd,
     // Keywords
-    And, Class, Else, False, For, Fun, If, Nil, Or,
+    And, Break, Class, Continue, Else, False, For, Fun, If, Nil, Or,
     Print, Return, Super, This, True, Var, While,
+    Test, Assert, AssertEq,
     // Special tokens
-    Error(String), Eof,
+    Eof,
     // Import keyword
     Import, From
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, serde::Deserialize)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub line: usize,
+    /// Byte offset of the token's first character in the source.
+    #[serde(default)]
+    pub offset: usize,
+    /// 1-based column of the token's first character; 0 for tokens that
+    /// didn't come from scanning a source file.
+    #[serde(default)]
+    pub column: usize,
+    /// Length of the token in the source, in bytes.
+    #[serde(default)]
+    pub length: usize,
 }
 
 impl Token {
+    /// Creates a token with no position in any source file.
     pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Self {
-        Token { token_type, lexeme, line }
+        Token {
+            token_type,
+            lexeme,
+            line,
+            offset: 0,
+            column: 0,
+            length: 0,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            line: self.line,
+            column: self.column,
+            offset: self.offset,
+            length: self.length,
+        }
     }
 }
\ No newline at end of file
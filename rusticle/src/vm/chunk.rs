@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::parser::ast::{Object, Stmt};
+use crate::utils::token::Token;
+
+/// One VM instruction. Operands index the tables of the chunk holding the
+/// instruction, count values on the stack, or are jump targets in `code`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    /// Pushes `constants[i]`.
+    Constant(u32),
+    Nil,
+    True,
+    False,
+    Pop,
+    /// Pushes copies of the given number of values from the top of the
+    /// stack, in the same order.
+    Duplicate(u32),
+    /// Local slots count from the called function's own slot.
+    GetLocal(u32),
+    SetLocal(u32),
+    GetUpvalue(u32),
+    SetUpvalue(u32),
+    /// Global and property operands index `names`.
+    GetGlobal(u32),
+    DefineGlobal(u32),
+    SetGlobal(u32),
+    GetProperty(u32),
+    SetProperty(u32),
+    /// Pops a superclass and `this`, and pushes the superclass's method
+    /// bound to `this`.
+    GetSuper(u32),
+    /// Applies the operator token the instruction came from to the top two
+    /// values.
+    Binary,
+    Unary,
+    Index,
+    IndexSet,
+    /// Pops whichever bounds are present, then the sliced value.
+    Slice { start: bool, end: bool },
+    /// Replaces the top value with the text `likh` would show for it, so
+    /// later changes to a list or map being printed don't show up.
+    Stringify,
+    List(u32),
+    /// Pops the given number of key/value pairs.
+    Map(u32),
+    Interpolate(u32),
+    Jump(u32),
+    /// Jumps when the top value is falsey, leaving it on the stack.
+    JumpIfFalse(u32),
+    JumpIfTrue(u32),
+    /// Calls the value below the given number of arguments.
+    Call(u32),
+    /// Pushes a closure over `functions[i]`.
+    Closure(u32),
+    /// Moves the local on top of the stack into the upvalue capturing it,
+    /// then pops it.
+    CloseUpvalue,
+    Return,
+    /// Pops `methods` closures and pushes a class made from them. When it
+    /// `inherits`, the superclass is the value just below the methods.
+    Class { name: u32, methods: u32, inherits: bool },
+    Print(u32),
+    Assert,
+    AssertEq,
+    /// Runs `imports[i]` on the tree-walking interpreter, which loads the
+    /// packaged function into the shared globals.
+    Import(u32),
+}
+
+/// A compiled function body.
+#[derive(Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    /// For each instruction, where in `tokens` its source token is. Errors
+    /// point at that token, and `Binary`/`Unary` take their operator from it.
+    pub origins: Vec<u32>,
+    pub tokens: Vec<Token>,
+    pub constants: Vec<Object>,
+    pub names: Vec<String>,
+    pub functions: Vec<Rc<CompiledFunction>>,
+    pub imports: Vec<Stmt>,
+}
+
+impl Chunk {
+    /// Appends an instruction, returning its index for later patching.
+    pub fn emit(&mut self, op: Op, token: &Token) -> usize {
+        if self.tokens.last() != Some(token) {
+            self.tokens.push(token.clone());
+        }
+        self.origins.push(self.tokens.len() as u32 - 1);
+        self.code.push(op);
+        self.code.len() - 1
+    }
+
+    pub fn token_at(&self, index: usize) -> &Token {
+        &self.tokens[self.origins[index] as usize]
+    }
+
+    pub fn add_constant(&mut self, value: Object) -> u32 {
+        self.constants.push(value);
+        self.constants.len() as u32 - 1
+    }
+
+    pub fn add_name(&mut self, name: &str) -> u32 {
+        match self.names.iter().position(|existing| existing == name) {
+            Some(index) => index as u32,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() as u32 - 1
+            }
+        }
+    }
+
+    pub fn add_function(&mut self, function: CompiledFunction) -> u32 {
+        self.functions.push(Rc::new(function));
+        self.functions.len() as u32 - 1
+    }
+
+    pub fn add_import(&mut self, import: Stmt) -> u32 {
+        self.imports.push(import);
+        self.imports.len() as u32 - 1
+    }
+
+    /// A readable listing of the instructions for `--disassemble`: index,
+    /// source line (`|` when unchanged), instruction, and what its operand
+    /// refers to.
+    fn disassemble(&self, name: &str, out: &mut String) {
+        out.push_str(&format!("== {} ==\n", name));
+        let mut previous_line = None;
+        for (index, op) in self.code.iter().enumerate() {
+            let token = self.token_at(index);
+            let line = if previous_line == Some(token.line) {
+                "   |".to_string()
+            } else {
+                format!("{:4}", token.line)
+            };
+            previous_line = Some(token.line);
+
+            let detail = match *op {
+                Op::Constant(i) => self.constants[i as usize].repr(),
+                Op::GetGlobal(i)
+                | Op::DefineGlobal(i)
+                | Op::SetGlobal(i)
+                | Op::GetProperty(i)
+                | Op::SetProperty(i)
+                | Op::GetSuper(i)
+                | Op::Class { name: i, .. } => self.names[i as usize].clone(),
+                Op::Binary | Op::Unary => token.lexeme.clone(),
+                Op::Closure(i) => format!("<functio {}>", self.functions[i as usize].name),
+                Op::Import(i) => match &self.imports[i as usize] {
+                    Stmt::Import { function_name, package_name, .. } => {
+                        format!("{:?} from {:?}", function_name, package_name)
+                    }
+                    _ => String::new(),
+                },
+                _ => String::new(),
+            };
+            let instruction = format!("{:?}", op);
+            let text = format!("{:04} {} {:<28} {}", index, line, instruction, detail);
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+
+        for function in &self.functions {
+            out.push('\n');
+            function.chunk.disassemble(&format!("<functio {}>", function.name), out);
+        }
+    }
+}
+
+/// Where a closure finds one of its upvalues when it is created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capture {
+    /// A local slot of the function creating the closure.
+    Local(u32),
+    /// An upvalue of the function creating the closure.
+    Upvalue(u32),
+    /// `this`, filled in when a method is bound to an instance. Always
+    /// upvalue 0 of a method.
+    Receiver,
+}
+
+pub struct CompiledFunction {
+    /// `<script>` for the top level of a program.
+    pub name: String,
+    pub arity: usize,
+    pub chunk: Chunk,
+    pub captures: Vec<Capture>,
+}
+
+impl CompiledFunction {
+    pub fn new(name: &str) -> Self {
+        CompiledFunction {
+            name: name.to_string(),
+            arity: 0,
+            chunk: Chunk::default(),
+            captures: Vec::new(),
+        }
+    }
+
+    /// The listing of this function and every function nested in it.
+    pub fn disassemble(&self) -> String {
+        let mut out = String::new();
+        self.chunk.disassemble(&self.name, &mut out);
+        out
+    }
+}
+
+/// A variable captured by a closure. It stays on the stack while the
+/// function that declared it is running and moves into the upvalue when
+/// its scope ends.
+pub enum Upvalue {
+    Open(usize),
+    Closed(Object),
+}
+
+/// A compiled function together with the variables it captured.
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<functio {}>", self.function.name)
+    }
+}
+
+impl Closure {
+    /// Returns a copy of this method with `this` bound to `instance`.
+    pub fn bind(&self, instance: Object) -> Closure {
+        let mut upvalues = self.upvalues.clone();
+        upvalues[0] = Rc::new(RefCell::new(Upvalue::Closed(instance)));
+        Closure {
+            function: self.function.clone(),
+            upvalues,
+        }
+    }
+}
@@ -0,0 +1,710 @@
+use crate::interpreter::visitor::Visitor;
+use crate::parser::ast::{Expr, Object, Stmt};
+use crate::utils::token::{Token, TokenType};
+use crate::vm::chunk::{Capture, Chunk, CompiledFunction, Op};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+struct Local {
+    name: String,
+    depth: usize,
+    /// Set once a closure captures the local, so leaving its scope moves it
+    /// into the upvalue instead of just popping it.
+    captured: bool,
+}
+
+/// Jumps out of a loop waiting for the address they go to.
+struct Loop {
+    /// Scope depth outside the loop body; locals deeper than this are
+    /// discarded by `ruk` and `agla`.
+    scope_depth: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// A function being compiled. Functions nest, so the compiler keeps a
+/// stack of these.
+struct FunctionState {
+    function: CompiledFunction,
+    kind: FunctionKind,
+    /// Stack slots of the running function, slot 0 holding the function
+    /// itself.
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+}
+
+impl FunctionState {
+    fn new(name: &str, kind: FunctionKind) -> Self {
+        let mut function = CompiledFunction::new(name);
+        if let FunctionKind::Method | FunctionKind::Initializer = kind {
+            function.captures.push(Capture::Receiver);
+        }
+        FunctionState {
+            function,
+            kind,
+            locals: vec![Local {
+                name: String::new(),
+                depth: 0,
+                captured: false,
+            }],
+            scope_depth: 0,
+            loops: Vec::new(),
+        }
+    }
+}
+
+/// Compiles a resolved program to bytecode for the VM. Variables are
+/// resolved again here, into stack slots, upvalues and globals, following
+/// the same scoping rules as the resolver.
+pub struct Compiler {
+    functions: Vec<FunctionState>,
+    /// Token the next instruction is attributed to.
+    token: Token,
+}
+
+impl Compiler {
+    pub fn compile(statements: &[Stmt]) -> CompiledFunction {
+        let mut compiler = Compiler {
+            functions: vec![FunctionState::new("<script>", FunctionKind::Script)],
+            token: Token::new(TokenType::Eof, String::new(), 0),
+        };
+        for statement in statements {
+            statement.accept(&mut compiler);
+        }
+        compiler.emit_return();
+        compiler.functions.pop().unwrap().function
+    }
+
+    fn current(&mut self) -> &mut FunctionState {
+        self.functions.last_mut().unwrap()
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.current().function.chunk
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        let token = self.token.clone();
+        self.chunk().emit(op, &token)
+    }
+
+    fn emit_constant(&mut self, value: Object) {
+        let index = self.chunk().add_constant(value);
+        self.emit(Op::Constant(index));
+    }
+
+    /// A function without a `dede` returns `nil`; an initializer returns
+    /// `this`.
+    fn emit_return(&mut self) {
+        if self.current().kind == FunctionKind::Initializer {
+            self.emit(Op::GetUpvalue(0));
+        } else {
+            self.emit(Op::Nil);
+        }
+        self.emit(Op::Return);
+    }
+
+    /// Sets the target of a jump emitted earlier to the next instruction.
+    fn patch_jump(&mut self, index: usize) {
+        let target = self.chunk().code.len() as u32;
+        let chunk = self.chunk();
+        chunk.code[index] = match chunk.code[index] {
+            Op::Jump(_) => Op::Jump(target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::JumpIfTrue(_) => Op::JumpIfTrue(target),
+            op => panic!("Expected a jump but found {:?}", op),
+        };
+    }
+
+    fn begin_scope(&mut self) {
+        self.current().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.current().scope_depth -= 1;
+        let depth = self.current().scope_depth;
+        self.discard_locals(depth);
+        let state = self.current();
+        while state.locals.last().is_some_and(|local| local.depth > depth) {
+            state.locals.pop();
+        }
+    }
+
+    /// Emits the pops for every local deeper than `depth`, leaving the
+    /// compiler's view of them alone so `ruk` and `agla` can use it too.
+    fn discard_locals(&mut self, depth: usize) {
+        let ops: Vec<Op> = self
+            .current()
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth > depth)
+            .map(|local| if local.captured { Op::CloseUpvalue } else { Op::Pop })
+            .collect();
+        for op in ops {
+            self.emit(op);
+        }
+    }
+
+    fn add_local(&mut self, name: &str) {
+        let state = self.current();
+        state.locals.push(Local {
+            name: name.to_string(),
+            depth: state.scope_depth,
+            captured: false,
+        });
+    }
+
+    /// Stores the value on top of the stack in a new variable: a global at
+    /// the top level of the program, a stack slot anywhere else.
+    fn define_variable(&mut self, name: &Token) {
+        if self.current().scope_depth > 0 {
+            self.add_local(&name.lexeme);
+        } else {
+            let index = self.chunk().add_name(&name.lexeme);
+            self.emit(Op::DefineGlobal(index));
+        }
+    }
+
+    fn resolve_local(state: &FunctionState, name: &str) -> Option<u32> {
+        state
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|slot| slot as u32)
+    }
+
+    /// Finds `name` in the functions enclosing `functions[level]`, adding
+    /// the upvalues that carry it down to that function.
+    fn resolve_upvalue(&mut self, level: usize, name: &str) -> Option<u32> {
+        if name == "this" {
+            if let FunctionKind::Method | FunctionKind::Initializer = self.functions[level].kind {
+                return Some(0);
+            }
+        }
+        let enclosing = level.checked_sub(1)?;
+
+        if let Some(slot) = Self::resolve_local(&self.functions[enclosing], name) {
+            self.functions[enclosing].locals[slot as usize].captured = true;
+            return Some(self.add_upvalue(level, Capture::Local(slot)));
+        }
+        let upvalue = self.resolve_upvalue(enclosing, name)?;
+        Some(self.add_upvalue(level, Capture::Upvalue(upvalue)))
+    }
+
+    fn add_upvalue(&mut self, level: usize, capture: Capture) -> u32 {
+        let captures = &mut self.functions[level].function.captures;
+        match captures.iter().position(|existing| *existing == capture) {
+            Some(index) => index as u32,
+            None => {
+                captures.push(capture);
+                captures.len() as u32 - 1
+            }
+        }
+    }
+
+    fn get_variable(&mut self, name: &str) {
+        let level = self.functions.len() - 1;
+        let op = if let Some(slot) = Self::resolve_local(&self.functions[level], name) {
+            Op::GetLocal(slot)
+        } else if let Some(index) = self.resolve_upvalue(level, name) {
+            Op::GetUpvalue(index)
+        } else {
+            Op::GetGlobal(self.chunk().add_name(name))
+        };
+        self.emit(op);
+    }
+
+    fn set_variable(&mut self, name: &str) {
+        let level = self.functions.len() - 1;
+        let op = if let Some(slot) = Self::resolve_local(&self.functions[level], name) {
+            Op::SetLocal(slot)
+        } else if let Some(index) = self.resolve_upvalue(level, name) {
+            Op::SetUpvalue(index)
+        } else {
+            Op::SetGlobal(self.chunk().add_name(name))
+        };
+        self.emit(op);
+    }
+
+    /// Compiles a function body and leaves a closure over it on the stack.
+    fn function(&mut self, name: &Token, params: &[Token], body: &[Stmt], kind: FunctionKind) {
+        let mut state = FunctionState::new(&name.lexeme, kind);
+        state.function.arity = params.len();
+        self.functions.push(state);
+
+        // no matching `end_scope`: returning discards the whole frame
+        self.begin_scope();
+        for param in params {
+            self.add_local(&param.lexeme);
+        }
+        for statement in body {
+            statement.accept(self);
+        }
+        self.emit_return();
+
+        let function = self.functions.pop().unwrap().function;
+        self.token = name.clone();
+        let index = self.chunk().add_function(function);
+        self.emit(Op::Closure(index));
+    }
+
+    fn loop_jump(&mut self, keyword: &Token, is_break: bool) {
+        self.token = keyword.clone();
+        let depth = self
+            .current()
+            .loops
+            .last()
+            .expect("the parser rejects 'ruk' and 'agla' outside of a loop")
+            .scope_depth;
+        self.discard_locals(depth);
+        let jump = self.emit(Op::Jump(0));
+        let innermost = self.current().loops.last_mut().unwrap();
+        if is_break {
+            innermost.breaks.push(jump);
+        } else {
+            innermost.continues.push(jump);
+        }
+    }
+}
+
+impl Visitor<()> for Compiler {
+    fn visit_assign_expr(&mut self, expr: &Expr) {
+        if let Expr::Assign { name, value, .. } = expr {
+            value.accept(self);
+            self.token = name.clone();
+            self.set_variable(&name.lexeme);
+        }
+    }
+
+    fn visit_binary_expr(&mut self, expr: &Expr) {
+        if let Expr::Binary { left, operator, right } = expr {
+            left.accept(self);
+            right.accept(self);
+            self.token = operator.clone();
+            self.emit(Op::Binary);
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { callee, paren, arguments } = expr {
+            callee.accept(self);
+            for argument in arguments {
+                argument.accept(self);
+            }
+            self.token = paren.clone();
+            self.emit(Op::Call(arguments.len() as u32));
+        }
+    }
+
+    fn visit_compound_set_expr(&mut self, expr: &Expr) {
+        if let Expr::CompoundSet { object, name, operator, value } = expr {
+            object.accept(self);
+            self.token = name.clone();
+            let index = self.chunk().add_name(&name.lexeme);
+            // one copy to read the property from and one to write it to
+            self.emit(Op::Duplicate(1));
+            self.emit(Op::GetProperty(index));
+            value.accept(self);
+            self.token = operator.clone();
+            self.emit(Op::Binary);
+            self.token = name.clone();
+            self.emit(Op::SetProperty(index));
+        }
+    }
+
+    fn visit_compound_index_set_expr(&mut self, expr: &Expr) {
+        if let Expr::CompoundIndexSet { object, bracket, index, operator, value } = expr {
+            object.accept(self);
+            index.accept(self);
+            self.token = bracket.clone();
+            self.emit(Op::Duplicate(2));
+            self.emit(Op::Index);
+            value.accept(self);
+            self.token = operator.clone();
+            self.emit(Op::Binary);
+            self.token = bracket.clone();
+            self.emit(Op::IndexSet);
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) {
+        if let Expr::Get { object, name } = expr {
+            object.accept(self);
+            self.token = name.clone();
+            let index = self.chunk().add_name(&name.lexeme);
+            self.emit(Op::GetProperty(index));
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        if let Expr::Grouping { expression } = expr {
+            expression.accept(self);
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) {
+        if let Expr::Index { object, bracket, index } = expr {
+            object.accept(self);
+            index.accept(self);
+            self.token = bracket.clone();
+            self.emit(Op::Index);
+        }
+    }
+
+    fn visit_index_set_expr(&mut self, expr: &Expr) {
+        if let Expr::IndexSet { object, bracket, index, value } = expr {
+            object.accept(self);
+            index.accept(self);
+            value.accept(self);
+            self.token = bracket.clone();
+            self.emit(Op::IndexSet);
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) {
+        if let Expr::Interpolation { parts } = expr {
+            for part in parts {
+                part.accept(self);
+                self.emit(Op::Stringify);
+            }
+            self.emit(Op::Interpolate(parts.len() as u32));
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) {
+        if let Expr::List { elements } = expr {
+            for element in elements {
+                element.accept(self);
+            }
+            self.emit(Op::List(elements.len() as u32));
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) {
+        if let Expr::Map { brace, entries } = expr {
+            for (key, value) in entries {
+                key.accept(self);
+                value.accept(self);
+            }
+            self.token = brace.clone();
+            self.emit(Op::Map(entries.len() as u32));
+        }
+    }
+
+    fn visit_literal_expr(&mut self, expr: &Expr) {
+        if let Expr::Literal { value } = expr {
+            match value {
+                Object::Nil => {
+                    self.emit(Op::Nil);
+                }
+                Object::Bool(true) => {
+                    self.emit(Op::True);
+                }
+                Object::Bool(false) => {
+                    self.emit(Op::False);
+                }
+                _ => self.emit_constant(value.clone()),
+            }
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) {
+        if let Expr::Logical { left, operator, right } = expr {
+            left.accept(self);
+            self.token = operator.clone();
+            // the left value is the result when it settles the answer
+            let jump = if operator.token_type == TokenType::Or {
+                self.emit(Op::JumpIfTrue(0))
+            } else {
+                self.emit(Op::JumpIfFalse(0))
+            };
+            self.emit(Op::Pop);
+            right.accept(self);
+            self.patch_jump(jump);
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) {
+        if let Expr::Set { object, name, value } = expr {
+            object.accept(self);
+            value.accept(self);
+            self.token = name.clone();
+            let index = self.chunk().add_name(&name.lexeme);
+            self.emit(Op::SetProperty(index));
+        }
+    }
+
+    fn visit_slice_expr(&mut self, expr: &Expr) {
+        if let Expr::Slice { object, bracket, start, end } = expr {
+            object.accept(self);
+            if let Some(start) = start {
+                start.accept(self);
+            }
+            if let Some(end) = end {
+                end.accept(self);
+            }
+            self.token = bracket.clone();
+            self.emit(Op::Slice {
+                start: start.is_some(),
+                end: end.is_some(),
+            });
+        }
+    }
+
+    fn visit_super_expr(&mut self, expr: &Expr) {
+        if let Expr::Super { keyword, method, .. } = expr {
+            self.token = keyword.clone();
+            self.get_variable("this");
+            self.get_variable("super");
+            self.token = method.clone();
+            let index = self.chunk().add_name(&method.lexeme);
+            self.emit(Op::GetSuper(index));
+        }
+    }
+
+    fn visit_this_expr(&mut self, expr: &Expr) {
+        if let Expr::This { keyword, .. } = expr {
+            self.token = keyword.clone();
+            self.get_variable("this");
+        }
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) {
+        if let Expr::Unary { operator, right } = expr {
+            right.accept(self);
+            self.token = operator.clone();
+            self.emit(Op::Unary);
+        }
+    }
+
+    fn visit_variable_expr(&mut self, expr: &Expr) {
+        if let Expr::Variable { name, .. } = expr {
+            self.token = name.clone();
+            self.get_variable(&name.lexeme);
+        }
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Block { statements } = stmt {
+            self.begin_scope();
+            for statement in statements {
+                statement.accept(self);
+            }
+            self.end_scope();
+        }
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Class { name, superclass, methods } = stmt {
+            self.token = name.clone();
+            // a local class gets its slot up front so its methods can capture it
+            let slot = if self.current().scope_depth > 0 {
+                self.emit(Op::Nil);
+                self.add_local(&name.lexeme);
+                Some(self.current().locals.len() as u32 - 1)
+            } else {
+                None
+            };
+
+            // methods of a subclass capture a local holding `super`
+            if let Some(superclass) = superclass {
+                superclass.accept(self);
+                self.begin_scope();
+                self.add_local("super");
+            }
+
+            for method in methods {
+                if let Stmt::Function { name, params, body } = method {
+                    let kind = if name.lexeme == "init" {
+                        FunctionKind::Initializer
+                    } else {
+                        FunctionKind::Method
+                    };
+                    self.function(name, params, body, kind);
+                }
+            }
+
+            self.token = match superclass {
+                Some(Expr::Variable { name, .. }) => name.clone(),
+                _ => name.clone(),
+            };
+            let index = self.chunk().add_name(&name.lexeme);
+            self.emit(Op::Class {
+                name: index,
+                methods: methods.len() as u32,
+                inherits: superclass.is_some(),
+            });
+
+            self.token = name.clone();
+            match slot {
+                Some(slot) => {
+                    self.emit(Op::SetLocal(slot));
+                    self.emit(Op::Pop);
+                }
+                None => {
+                    self.emit(Op::DefineGlobal(index));
+                }
+            }
+
+            if superclass.is_some() {
+                self.end_scope();
+            }
+        }
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Expression { expression } = stmt {
+            expression.accept(self);
+            self.emit(Op::Pop);
+        }
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Function { name, params, body } = stmt {
+            // declared before the body so it can call itself
+            if self.current().scope_depth > 0 {
+                self.add_local(&name.lexeme);
+                self.function(name, params, body, FunctionKind::Function);
+            } else {
+                self.function(name, params, body, FunctionKind::Function);
+                self.define_variable(name);
+            }
+        }
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::If { condition, then_branch, else_branch } = stmt {
+            condition.accept(self);
+            let else_jump = self.emit(Op::JumpIfFalse(0));
+            self.emit(Op::Pop);
+            then_branch.accept(self);
+            let end_jump = self.emit(Op::Jump(0));
+
+            self.patch_jump(else_jump);
+            self.emit(Op::Pop);
+            if let Some(else_branch) = else_branch {
+                else_branch.accept(self);
+            }
+            self.patch_jump(end_jump);
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Print { expressions } = stmt {
+            for expression in expressions {
+                expression.accept(self);
+                self.emit(Op::Stringify);
+            }
+            self.emit(Op::Print(expressions.len() as u32));
+        }
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Return { keyword, value } = stmt {
+            self.token = keyword.clone();
+            match value {
+                Some(value) => {
+                    value.accept(self);
+                    self.emit(Op::Return);
+                }
+                None => self.emit_return(),
+            }
+        }
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Var { name, initializer } = stmt {
+            self.token = name.clone();
+            match initializer {
+                Some(initializer) => initializer.accept(self),
+                None => {
+                    self.emit(Op::Nil);
+                }
+            }
+            self.token = name.clone();
+            self.define_variable(name);
+        }
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::While { condition, body, increment } = stmt {
+            let start = self.chunk().code.len() as u32;
+            condition.accept(self);
+            let exit_jump = self.emit(Op::JumpIfFalse(0));
+            self.emit(Op::Pop);
+
+            let scope_depth = self.current().scope_depth;
+            self.current().loops.push(Loop {
+                scope_depth,
+                breaks: Vec::new(),
+                continues: Vec::new(),
+            });
+            body.accept(self);
+            let finished = self.current().loops.pop().unwrap();
+
+            // `agla` still runs the increment
+            for jump in finished.continues {
+                self.patch_jump(jump);
+            }
+            if let Some(increment) = increment {
+                increment.accept(self);
+                self.emit(Op::Pop);
+            }
+            self.emit(Op::Jump(start));
+
+            self.patch_jump(exit_jump);
+            self.emit(Op::Pop);
+            for jump in finished.breaks {
+                self.patch_jump(jump);
+            }
+        }
+    }
+
+    fn visit_break_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Break { keyword } = stmt {
+            self.loop_jump(keyword, true);
+        }
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Continue { keyword } = stmt {
+            self.loop_jump(keyword, false);
+        }
+    }
+
+    // tests are only run by `rusticle test`
+    fn visit_test_stmt(&mut self, _stmt: &Stmt) {}
+
+    fn visit_assert_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Assert { keyword, condition } = stmt {
+            condition.accept(self);
+            self.token = keyword.clone();
+            self.emit(Op::Assert);
+        }
+    }
+
+    fn visit_assert_eq_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::AssertEq { keyword, left, right } = stmt {
+            left.accept(self);
+            right.accept(self);
+            self.token = keyword.clone();
+            self.emit(Op::AssertEq);
+        }
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Import { keyword, .. } = stmt {
+            self.token = keyword.clone();
+            let index = self.chunk().add_import(stmt.clone());
+            self.emit(Op::Import(index));
+        }
+    }
+}
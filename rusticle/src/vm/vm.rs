@@ -0,0 +1,422 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::interpreter::interpreter::{Interpreter, Unwind};
+use crate::interpreter::operations;
+use crate::parser::ast::{Class, Instance, Object};
+use crate::utils::error::RuntimeError;
+use crate::utils::token::Token;
+use crate::vm::chunk::{Capture, Closure, CompiledFunction, Op, Upvalue};
+
+/// A running call: the closure, the next instruction in its chunk, and
+/// where its slots start on the stack.
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,
+    base: usize,
+}
+
+/// Runs compiled programs. Globals, input, output and the policy belong to
+/// the wrapped interpreter, which also runs natives, imports and packaged
+/// functions, so both engines share a single runtime.
+pub struct Vm {
+    pub interpreter: Interpreter,
+    stack: Vec<Object>,
+    frames: Vec<Frame>,
+    /// Upvalues still pointing into the stack, so closures created in the
+    /// same scope share them.
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Vm::new(Interpreter::new())
+    }
+}
+
+impl Vm {
+    pub fn new(interpreter: Interpreter) -> Self {
+        Vm {
+            interpreter,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            open_upvalues: Vec::new(),
+        }
+    }
+
+    /// Runs the top level of a program compiled by `Compiler::compile`.
+    pub fn run(&mut self, function: CompiledFunction) -> Result<(), RuntimeError> {
+        let script = Rc::new(Closure {
+            function: Rc::new(function),
+            upvalues: Vec::new(),
+        });
+        self.stack.push(Object::Closure(script.clone()));
+        self.frames.push(Frame {
+            closure: script,
+            ip: 0,
+            base: 0,
+        });
+
+        let result = self.execute();
+        if result.is_err() {
+            // leave the machine reusable after a runtime error
+            self.stack.clear();
+            self.frames.clear();
+            self.open_upvalues.clear();
+        }
+        result
+    }
+
+    fn execute(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let closure = frame.closure.clone();
+            let ip = frame.ip;
+            let base = frame.base;
+            frame.ip += 1;
+
+            let chunk = &closure.function.chunk;
+            let token = chunk.token_at(ip);
+            match chunk.code[ip] {
+                Op::Constant(index) => self.stack.push(chunk.constants[index as usize].clone()),
+                Op::Nil => self.stack.push(Object::Nil),
+                Op::True => self.stack.push(Object::Bool(true)),
+                Op::False => self.stack.push(Object::Bool(false)),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Duplicate(count) => {
+                    let copies = self.stack[self.stack.len() - count as usize..].to_vec();
+                    self.stack.extend(copies);
+                }
+                Op::GetLocal(slot) => {
+                    let value = self.stack[base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Op::SetLocal(slot) => {
+                    self.stack[base + slot as usize] = self.peek(0).clone();
+                }
+                Op::GetUpvalue(index) => {
+                    let value = match &*closure.upvalues[index as usize].borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                }
+                Op::SetUpvalue(index) => {
+                    let value = self.peek(0).clone();
+                    match &mut *closure.upvalues[index as usize].borrow_mut() {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+                Op::GetGlobal(index) => {
+                    let name = &chunk.names[index as usize];
+                    let value = self.interpreter.globals.borrow().get_at(0, name);
+                    match value {
+                        Some(value) => self.stack.push(value),
+                        None => {
+                            let message = format!("Undefined variable '{}'.", name);
+                            return Err(RuntimeError::new(token, &message));
+                        }
+                    }
+                }
+                Op::DefineGlobal(index) => {
+                    let value = self.pop();
+                    let name = chunk.names[index as usize].clone();
+                    self.interpreter.globals.borrow_mut().define(name, value);
+                }
+                Op::SetGlobal(_) => {
+                    let value = self.peek(0).clone();
+                    self.interpreter
+                        .globals
+                        .borrow_mut()
+                        .assign(token, value)
+                        .map_err(|message| RuntimeError::new(token, &message))?;
+                }
+                Op::GetProperty(index) => {
+                    let name = &chunk.names[index as usize];
+                    let Object::Instance(instance) = self.pop() else {
+                        return Err(RuntimeError::new(token, "Only instances have properties."));
+                    };
+                    let value = instance.borrow().field(name);
+                    let value = match value {
+                        Some(value) => value,
+                        None => {
+                            let class = instance.borrow().class.clone();
+                            let method = class.find_compiled(name).ok_or_else(|| {
+                                let message = format!("Undefined property '{}'.", name);
+                                RuntimeError::new(token, &message)
+                            })?;
+                            Object::Closure(Rc::new(method.bind(Object::Instance(instance))))
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                Op::SetProperty(_) => {
+                    let value = self.pop();
+                    let Object::Instance(instance) = self.pop() else {
+                        return Err(RuntimeError::new(token, "Only instances have fields."));
+                    };
+                    instance.borrow_mut().set(token, value.clone());
+                    self.stack.push(value);
+                }
+                Op::GetSuper(index) => {
+                    let name = &chunk.names[index as usize];
+                    let Object::Class(superclass) = self.pop() else {
+                        panic!("Expected 'super' to be a class");
+                    };
+                    let instance = self.pop();
+                    let method = superclass.find_compiled(name).ok_or_else(|| {
+                        let message = format!("Undefined property '{}'.", name);
+                        RuntimeError::new(token, &message)
+                    })?;
+                    self.stack.push(Object::Closure(Rc::new(method.bind(instance))));
+                }
+                Op::Binary => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(operations::binary(token, &left, &right)?);
+                }
+                Op::Unary => {
+                    let right = self.pop();
+                    self.stack.push(operations::unary(token, &right)?);
+                }
+                Op::Index => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.stack.push(operations::index(token, &object, &index)?);
+                }
+                Op::IndexSet => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    operations::index_set(token, &object, &index, value.clone())?;
+                    self.stack.push(value);
+                }
+                Op::Slice { start, end } => {
+                    let end = if end { Some(self.pop()) } else { None };
+                    let start = if start { Some(self.pop()) } else { None };
+                    let object = self.pop();
+                    let sliced = operations::slice(token, &object, start.as_ref(), end.as_ref())?;
+                    self.stack.push(sliced);
+                }
+                Op::Stringify => {
+                    let text = self.pop().to_string();
+                    self.stack.push(Object::String(text));
+                }
+                Op::List(count) => {
+                    let elements = self.pop_many(count as usize);
+                    self.stack.push(Object::List(Rc::new(RefCell::new(elements))));
+                }
+                Op::Map(pairs) => {
+                    let values = self.pop_many(pairs as usize * 2);
+                    let mut map = IndexMap::new();
+                    let mut values = values.into_iter();
+                    while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                        map.insert(operations::map_key(token, &key)?, value);
+                    }
+                    self.stack.push(Object::Map(Rc::new(RefCell::new(map))));
+                }
+                Op::Interpolate(count) => {
+                    let text: String = self
+                        .pop_many(count as usize)
+                        .iter()
+                        .map(|part| part.to_string())
+                        .collect();
+                    self.stack.push(Object::String(text));
+                }
+                Op::Jump(target) => self.jump(target),
+                Op::JumpIfFalse(target) => {
+                    if !self.peek(0).as_bool() {
+                        self.jump(target);
+                    }
+                }
+                Op::JumpIfTrue(target) => {
+                    if self.peek(0).as_bool() {
+                        self.jump(target);
+                    }
+                }
+                Op::Call(count) => self.call(token, count as usize)?,
+                Op::Closure(index) => {
+                    let function = chunk.functions[index as usize].clone();
+                    let upvalues = function
+                        .captures
+                        .iter()
+                        .map(|capture| match capture {
+                            Capture::Local(slot) => self.capture_upvalue(base + *slot as usize),
+                            Capture::Upvalue(index) => closure.upvalues[*index as usize].clone(),
+                            // filled in by `Closure::bind`
+                            Capture::Receiver => Rc::new(RefCell::new(Upvalue::Closed(Object::Nil))),
+                        })
+                        .collect();
+                    self.stack.push(Object::Closure(Rc::new(Closure { function, upvalues })));
+                }
+                Op::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                Op::Return => {
+                    let result = self.pop();
+                    self.close_upvalues(base);
+                    self.stack.truncate(base);
+                    self.frames.pop();
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+                    self.stack.push(result);
+                }
+                Op::Class { name, methods, inherits } => {
+                    let methods: HashMap<String, Rc<Closure>> = self
+                        .pop_many(methods as usize)
+                        .into_iter()
+                        .map(|method| match method {
+                            Object::Closure(method) => (method.function.name.clone(), method),
+                            _ => panic!("Expected a method closure"),
+                        })
+                        .collect();
+                    // the superclass stays on the stack as the `super` local
+                    let superclass = match (inherits, self.peek(0)) {
+                        (false, _) => None,
+                        (true, Object::Class(superclass)) => Some(superclass.clone()),
+                        (true, _) => {
+                            return Err(RuntimeError::new(token, "Superclass must be a class."));
+                        }
+                    };
+                    let name = chunk.names[name as usize].clone();
+                    let class = Class::compiled(name, superclass, methods);
+                    self.stack.push(Object::Class(Rc::new(class)));
+                }
+                Op::Print(count) => {
+                    let values: Vec<String> = self
+                        .pop_many(count as usize)
+                        .iter()
+                        .map(|value| value.to_string())
+                        .collect();
+                    // like `println!` into a closed pipe, output to a failed sink is lost
+                    let _ = writeln!(self.interpreter.output.borrow_mut(), "{}", values.join(" "));
+                }
+                Op::Assert => {
+                    if !self.pop().as_bool() {
+                        return Err(RuntimeError::new(token, "Assertion failed."));
+                    }
+                }
+                Op::AssertEq => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    if left != right {
+                        let message = format!(
+                            "Assertion failed: left is {} but right is {}.",
+                            left.repr(),
+                            right.repr()
+                        );
+                        return Err(RuntimeError::new(token, &message));
+                    }
+                }
+                Op::Import(index) => {
+                    let import = chunk.imports[index as usize].clone();
+                    self.interpreter.interpret(vec![import])?;
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().expect("the compiler keeps the stack balanced")
+    }
+
+    /// Pops `count` values, returned in the order they were pushed.
+    fn pop_many(&mut self, count: usize) -> Vec<Object> {
+        self.stack.split_off(self.stack.len() - count)
+    }
+
+    fn peek(&self, distance: usize) -> &Object {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn jump(&mut self, target: u32) {
+        self.frames.last_mut().unwrap().ip = target as usize;
+    }
+
+    /// Calls the value below the top `count` values with them as arguments.
+    /// Compiled code gets a new frame; anything else runs to completion
+    /// here and leaves its result in place of the callee.
+    fn call(&mut self, paren: &Token, count: usize) -> Result<(), RuntimeError> {
+        let slot = self.stack.len() - count - 1;
+        match self.stack[slot].clone() {
+            Object::Closure(closure) => self.call_closure(paren, closure, count),
+            Object::Class(class) => {
+                let instance = Object::Instance(Rc::new(RefCell::new(Instance::new(class.clone()))));
+                self.stack[slot] = instance.clone();
+                match class.find_compiled("init") {
+                    // the initializer returns `this`, which replaces the slot
+                    Some(init) => self.call_closure(paren, Rc::new(init.bind(instance)), count),
+                    None => operations::check_arity(paren, 0, count),
+                }
+            }
+            callee => {
+                let Some(callable) = callee.as_callable() else {
+                    return Err(RuntimeError::new(paren, "Can only call functions and classes."));
+                };
+                if let Some(arity) = callable.arity() {
+                    operations::check_arity(paren, arity, count)?;
+                }
+                let arguments = self.pop_many(count);
+                self.pop();
+                let result = callable
+                    .call(&mut self.interpreter, paren, arguments)
+                    .map_err(|unwind| match unwind {
+                        Unwind::Error(error) => error,
+                        _ => unreachable!("calls catch 'dede', 'ruk' and 'agla' inside them"),
+                    })?;
+                self.stack.push(result);
+                Ok(())
+            }
+        }
+    }
+
+    fn call_closure(&mut self, paren: &Token, closure: Rc<Closure>, count: usize) -> Result<(), RuntimeError> {
+        operations::check_arity(paren, closure.function.arity, count)?;
+        // the frame running the top level of the program isn't a call
+        if self.frames.len() > self.interpreter.max_call_depth {
+            return Err(RuntimeError::new(paren, "Stack overflow."));
+        }
+        self.frames.push(Frame {
+            closure,
+            ip: 0,
+            base: self.stack.len() - count - 1,
+        });
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        let existing = self
+            .open_upvalues
+            .iter()
+            .find(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(open) if open == slot));
+        if let Some(upvalue) = existing {
+            return upvalue.clone();
+        }
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        self.open_upvalues.push(upvalue.clone());
+        upvalue
+    }
+
+    /// Moves every open upvalue at or above `from` off the stack.
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let mut upvalue = upvalue.borrow_mut();
+            match *upvalue {
+                Upvalue::Open(slot) if slot >= from => {
+                    *upvalue = Upvalue::Closed(stack[slot].clone());
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+}
@@ -0,0 +1,78 @@
+//! Checks classes, instances, fields, methods and `this`.
+
+mod common;
+
+use common::{errors, same};
+
+#[test]
+fn instances_hold_fields_and_bind_this() {
+    same(
+        r#"
+        class Point {
+            init(x, y) { this.x = x; this.y = y; }
+            sum() { dede this.x + this.y; }
+        }
+        manle p = Point(1, 2);
+        p.x = 10;
+        likh p.sum(), p, Point;
+        manle sum = p.sum;
+        p.y = 5;
+        likh sum();
+        "#,
+        "12 Point instance Point\n15\n",
+    );
+}
+
+#[test]
+fn init_returns_the_instance() {
+    same(
+        r#"
+        class Box { init(v) { this.v = v; dede; } }
+        manle b = Box(1);
+        likh b.init(2) == b, b.v;
+        "#,
+        "true 2\n",
+    );
+    assert_eq!(
+        errors("class Bad { init() { dede 1; } }"),
+        ["[line 1] Error at 'dede': Can't return a value from an initializer."]
+    );
+}
+
+#[test]
+fn rejects_properties_of_non_instances() {
+    same("class A { }\nlikh A().missing;", "[line 2] RuntimeError: Undefined property 'missing'.");
+    same("manle n = 1; n.x = 2;", "[line 1] RuntimeError: Only instances have fields.");
+    same("likh \"s\".length;", "[line 1] RuntimeError: Only instances have properties.");
+}
+
+#[test]
+fn subclasses_inherit_and_call_up_with_super() {
+    same(
+        r#"
+        class Animal {
+            init(name) { this.name = name; }
+            speak() { dede this.name + " makes a sound"; }
+            describe() { dede this.speak(); }
+        }
+        class Dog < Animal {
+            speak() { dede super.speak() + ", woof"; }
+        }
+        class Puppy < Dog { }
+        likh Puppy("Rex").describe();
+        "#,
+        "Rex makes a sound, woof\n",
+    );
+}
+
+#[test]
+fn rejects_bad_superclasses() {
+    same("manle NotClass = 1;\nclass A < NotClass { }", "[line 2] RuntimeError: Superclass must be a class.");
+    assert_eq!(
+        errors("class A < A { }\nclass B { m() { super.m(); } }"),
+        [
+            "[line 1] Error at 'A': A class can't inherit from itself.",
+            "[line 2] Error at 'super': Can't use 'super' in a class with no superclass.",
+        ]
+    );
+}
@@ -0,0 +1,112 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::process::{Command, Output};
+use std::rc::Rc;
+use std::thread;
+
+use rusticle::commands::execute::resolve;
+use rusticle::interpreter::interpreter::{Interpreter, STACK_SIZE};
+use rusticle::lexer::lexer::Lexer;
+use rusticle::parser::ast::Stmt;
+use rusticle::parser::parser::Parser;
+use rusticle::vm::compiler::Compiler;
+use rusticle::utils::error::{ParseError, ResolveError};
+use rusticle::vm::vm::Vm;
+
+/// The ways the binary can run a program.
+#[derive(Clone, Copy, Debug)]
+pub enum Mode {
+    Tree,
+    Vm,
+    /// On the tree-walker after `--opt`.
+    Optimized,
+}
+
+/// Lexes and parses `source`, which must be free of syntax errors.
+pub fn parse(source: &str) -> Vec<Stmt> {
+    let tokens = Lexer::new(source.to_string()).scan_tokens().unwrap().clone();
+    Parser::new(tokens).parse().unwrap()
+}
+
+/// Parses and resolves `source` as the binary does before running it,
+/// optimizing it too when `optimize` is set.
+pub fn check(source: &str, optimize: bool) -> Result<Vec<Stmt>, Vec<ResolveError>> {
+    resolve(parse(source), Interpreter::new().global_names(), optimize)
+}
+
+/// The syntax errors in `source`, which must lex cleanly.
+pub fn parse_errors(source: &str) -> Vec<ParseError> {
+    let tokens = Lexer::new(source.to_string()).scan_tokens().unwrap().clone();
+    Parser::new(tokens).parse().err().unwrap_or_default()
+}
+
+/// The errors that stop `source` before it runs, from the first phase that
+/// finds any: lexing, parsing, then resolving.
+pub fn errors(source: &str) -> Vec<String> {
+    let tokens = match Lexer::new(source.to_string()).scan_tokens() {
+        Ok(tokens) => tokens.clone(),
+        Err(errors) => return errors.iter().map(|error| error.to_string()).collect(),
+    };
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) => statements,
+        Err(errors) => return errors.iter().map(|error| error.to_string()).collect(),
+    };
+    match resolve(statements, Interpreter::new().global_names(), false) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+    }
+}
+
+/// What a run printed, followed by its runtime error if it failed.
+pub fn run(source: &str, mode: Mode) -> String {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter = Interpreter::new();
+    interpreter.output = output.clone();
+
+    let statements = check(source, matches!(mode, Mode::Optimized)).unwrap();
+    let result = match mode {
+        Mode::Vm => Vm::new(interpreter).run(Compiler::compile(&statements)),
+        Mode::Tree | Mode::Optimized => interpreter.interpret(statements).map(|_| ()),
+    };
+
+    let mut printed = String::from_utf8(output.borrow().clone()).unwrap();
+    if let Err(error) = result {
+        printed += &error.to_string();
+    }
+    printed
+}
+
+/// Runs `f` with the stack the binary gives programs, which deep recursion
+/// needs.
+pub fn on_big_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    thread::Builder::new().stack_size(STACK_SIZE).spawn(f).unwrap().join().unwrap()
+}
+
+/// Checks the program gives `expected` in every mode.
+pub fn same(source: &str, expected: &str) {
+    let source = source.to_string();
+    let results = on_big_stack(move || {
+        [Mode::Tree, Mode::Vm, Mode::Optimized].map(|mode| (mode, run(&source, mode)))
+    });
+    for (mode, printed) in results {
+        assert_eq!(printed, expected, "unexpected result in {:?} mode", mode);
+    }
+}
+
+/// Runs the binary with `args` followed by a file holding `source`. `name`
+/// keeps the files of tests running at the same time apart.
+pub fn cli(name: &str, args: &[&str], source: &str) -> Output {
+    let path = env::temp_dir().join(format!("rusticle-{}-{}.lin", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rusticle"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    output
+}
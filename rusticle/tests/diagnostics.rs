@@ -0,0 +1,38 @@
+//! Checks that errors point at the right place in the source.
+
+mod common;
+
+use common::parse_errors;
+use rusticle::utils::diagnostic::render;
+use rusticle::{Engine, Error};
+
+#[test]
+fn underlines_the_token_a_runtime_error_is_about() {
+    let source = "manle x = 1;\nlikh x + \"a\";\n";
+    let error = match Engine::new().eval(source) {
+        Err(Error::Runtime(error)) => error,
+        other => panic!("expected a runtime error, got {:?}", other),
+    };
+    assert_eq!(
+        render(source, "main.lin", &error),
+        "[line 2] RuntimeError: Operands must be two numbers or two strings.\n \
+         --> main.lin:2:8\n  \
+         |\n\
+         2 | likh x + \"a\";\n  \
+         |        ^"
+    );
+}
+
+#[test]
+fn underlines_the_whole_token_a_syntax_error_is_at() {
+    let source = "likh \"héllo\" manle;";
+    let errors = parse_errors(source);
+    assert_eq!(
+        render(source, "main.lin", &errors[0]),
+        "[line 1] Error at 'manle': Expect ';' after value.\n \
+         --> main.lin:1:14\n  \
+         |\n\
+         1 | likh \"héllo\" manle;\n  \
+         |              ^~~~~"
+    );
+}
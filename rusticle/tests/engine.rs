@@ -0,0 +1,61 @@
+//! Checks Lin embedded in Rust through an `Engine`.
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::on_big_stack;
+use rusticle::interpreter::interpreter::MAX_CALL_DEPTH;
+use rusticle::{Engine, Error, Object};
+
+#[test]
+fn shares_globals_with_rust() {
+    let mut engine = Engine::new();
+    engine.set_global("limit", Object::Int(10));
+    engine.register_fn("double", |args| match args {
+        [Object::Int(n)] => Ok(Object::Int(n * 2)),
+        _ => Err("double() expects one integer.".to_string()),
+    });
+
+    assert_eq!(engine.eval("double(limit) + 1").unwrap(), Object::Int(21));
+    engine.eval("manle seen = limit;").unwrap();
+    assert_eq!(engine.get_global("seen"), Some(Object::Int(10)));
+
+    let error = engine.eval(r#"double("x");"#).unwrap_err();
+    assert_eq!(error.to_string(), "[line 1] RuntimeError: double() expects one integer.");
+}
+
+#[test]
+fn captures_what_scripts_print() {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut engine = Engine::new();
+    engine.set_output(output.clone());
+    engine.eval(r#"likh "hi", 1 + 1;"#).unwrap();
+    assert_eq!(String::from_utf8(output.borrow().clone()).unwrap(), "hi 2\n");
+}
+
+#[test]
+fn stops_runaway_recursion_on_an_ordinary_thread() {
+    let mut engine = Engine::new();
+    match engine.eval("functio f() { dede f(); } f();") {
+        Err(Error::Runtime(error)) => assert_eq!(error.message, "Stack overflow."),
+        other => panic!("expected a stack overflow, got {:?}", other),
+    }
+
+    // the engine is still usable afterwards
+    let value = engine.eval("functio g(n) { agar (n == 0) { dede 0; } dede g(n - 1) + 1; } g(150)");
+    assert_eq!(value.unwrap(), Object::Int(150));
+}
+
+#[test]
+fn recurses_further_on_a_bigger_stack() {
+    let value = on_big_stack(|| {
+        let mut engine = Engine::new();
+        engine.set_max_call_depth(MAX_CALL_DEPTH);
+        let source = "functio g(n) { agar (n == 0) { dede 0; } dede g(n - 1) + 1; } g(9999)";
+        // objects can't leave the thread, so it is compared as text
+        engine.eval(source).map(|value| value.to_string()).map_err(|error| error.to_string())
+    });
+    assert_eq!(value.as_deref(), Ok("9999"));
+}
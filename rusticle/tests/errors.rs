@@ -0,0 +1,30 @@
+//! Checks that mistakes in a program are reported as errors, with the exit
+//! status and source excerpt the binary gives them, instead of panicking.
+
+mod common;
+
+use common::{cli, same};
+
+#[test]
+fn runtime_errors_stop_the_program() {
+    same("likh 1;\nlikh undefined;\nlikh 2;", "1\n[line 2] RuntimeError: Undefined variable 'undefined'.");
+    same("manle x = 1; x();", "[line 1] RuntimeError: Can only call functions and classes.");
+    same("functio f(a) { }\nf();", "[line 2] RuntimeError: Expected 1 arguments but got 0.");
+    same("likh -\"a\";", "[line 1] RuntimeError: Operand must be a number.");
+}
+
+#[test]
+fn deep_recursion_is_a_runtime_error() {
+    same("functio f(n) { dede f(n + 1); }\nf(0);", "[line 1] RuntimeError: Stack overflow.");
+}
+
+#[test]
+fn the_binary_exits_with_the_kind_of_error() {
+    let runtime = cli("errors-runtime", &[], "likh 1;\nlikh nil + 1;\n");
+    assert_eq!(runtime.status.code(), Some(70));
+    assert_eq!(String::from_utf8_lossy(&runtime.stdout), "1\n");
+
+    let compile = cli("errors-compile", &[], "likh 1;\nlikh (;\n");
+    assert_eq!(compile.status.code(), Some(65));
+    assert_eq!(String::from_utf8_lossy(&compile.stdout), "");
+}
@@ -0,0 +1,36 @@
+//! Checks how `likh` prints values and what `format()` does with them.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn likh_prints_values_as_they_are_written() {
+    same(
+        r#"
+        functio f() { }
+        class A { }
+        likh 13, 13.0, "text", nil, true, f, A, A(), [1, "a", nil], #{"k": 2.5};
+        "#,
+        "13 13.0 text nil true <functio f> A A instance [1, \"a\", nil] #{\"k\": 2.5}\n",
+    );
+}
+
+#[test]
+fn formats_with_specifiers() {
+    same(
+        r#"
+        likh format("{:>6}|{:<6}|", 42, "ab");
+        likh format("{:.2} {:08.3}", 3.14159, -2.5);
+        likh format("{{braces}} {} {:*^7} {:.3}", nil, "mid", "truncate");
+        "#,
+        "    42|ab    |\n3.14 -002.500\n{braces} nil **mid** tru\n",
+    );
+}
+
+#[test]
+fn rejects_bad_templates() {
+    same(r#"likh format("{} {}", 1);"#, "[line 1] RuntimeError: Not enough values for the format template.");
+    same(r#"likh format("{:.70000}", 1.5);"#, "[line 1] RuntimeError: Invalid format specifier '.70000'.");
+    same(r#"likh format("{:70000}", 1);"#, "[line 1] RuntimeError: Invalid format specifier '70000'.");
+}
@@ -0,0 +1,73 @@
+//! Checks calling functions and returning from them with `dede`.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn dede_returns_from_nested_statements() {
+    same(
+        r#"
+        functio find(xs, wanted) {
+            for (manle i = 0; i < len(xs); i = i + 1) {
+                agar (xs[i] == wanted) {
+                    jabTak (true) { dede i; }
+                }
+            }
+            dede -1;
+        }
+        likh find([4, 5, 6], 6), find([4], 7);
+        "#,
+        "2 -1\n",
+    );
+}
+
+#[test]
+fn a_function_without_dede_gives_nil() {
+    same("functio f() { manle x = 1; } functio g() { dede; } likh f(), g();", "nil nil\n");
+}
+
+#[test]
+fn recursion_sees_each_call_separately() {
+    same(
+        "functio fib(n) { agar (n < 2) { dede n; } dede fib(n - 1) + fib(n - 2); } likh fib(15);",
+        "610\n",
+    );
+}
+
+#[test]
+fn closures_keep_their_defining_scope_alive() {
+    same(
+        r#"
+        functio counter() {
+            manle n = 0;
+            functio inc() { n = n + 1; dede n; }
+            dede inc;
+        }
+        manle a = counter();
+        manle b = counter();
+        a(); a();
+        likh a(), b();
+        "#,
+        "3 1\n",
+    );
+}
+
+#[test]
+fn closures_over_one_variable_share_it() {
+    same(
+        r#"
+        manle get; manle set;
+        functio pair() {
+            manle value = "first";
+            functio g() { dede value; }
+            functio s(v) { value = v; }
+            get = g; set = s;
+        }
+        pair();
+        set("second");
+        likh get();
+        "#,
+        "second\n",
+    );
+}
@@ -0,0 +1,25 @@
+//! Checks the lexer's errors, which stop a program before it is parsed.
+
+mod common;
+
+use common::errors;
+
+#[test]
+fn reports_every_lexical_error_with_its_column() {
+    assert_eq!(
+        errors("likh 1 @ 2;\nlikh \"ok\";\nmanle s = \"bad \\q escape\";"),
+        [
+            "[line 1, column 8] Error: Unexpected character '@'.",
+            "[line 3, column 16] Error: Invalid escape sequence '\\q'.",
+        ]
+    );
+}
+
+#[test]
+fn reports_unterminated_strings() {
+    assert_eq!(errors("likh \"open;"), ["[line 1, column 6] Error: Unterminated string."]);
+    assert_eq!(
+        errors("likh \"a ${1 + "),
+        ["[line 1, column 6] Error: Unterminated string interpolation."]
+    );
+}
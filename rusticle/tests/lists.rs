@@ -0,0 +1,70 @@
+//! Checks list literals, indexing, slicing, sharing and comparison.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn reads_and_replaces_elements() {
+    same(
+        r#"
+        manle xs = [1, "two", true];
+        likh xs[1];
+        xs[0] = 10;
+        xs[0] += 1;
+        push(xs, nil);
+        likh xs, len(xs);
+        likh pop(xs), xs[1:3], xs[:1], xs[2:];
+        "#,
+        "two\n[11, \"two\", true, nil] 4\nnil [\"two\", true] [11] [true]\n",
+    );
+}
+
+#[test]
+fn lists_are_shared() {
+    same(
+        "manle xs = [1]; manle ys = xs; push(ys, 2); likh xs, xs == ys, [1, 2] == xs;",
+        "[1, 2] true true\n",
+    );
+}
+
+#[test]
+fn evaluates_a_compound_element_target_once() {
+    same(
+        r#"
+        manle xs = [10, 20];
+        manle n = 0;
+        functio idx() { n += 1; dede 1; }
+        xs[idx()] += 1;
+        likh xs, n;
+        "#,
+        "[10, 21] 1\n",
+    );
+}
+
+#[test]
+fn rejects_indices_outside_the_list() {
+    same("manle xs = [1]; likh xs[-1];", "[line 1] RuntimeError: Index -1 is out of range for length 1.");
+    same("likh [1, 2][2:1];", "[line 1] RuntimeError: Slice start 2 is after its end 1.");
+}
+
+#[test]
+fn prints_a_list_that_contains_itself() {
+    same(
+        "manle xs = [1, 2]; push(xs, xs); likh xs, [xs];",
+        "[1, 2, [...]] [[1, 2, [...]]]\n",
+    );
+}
+
+#[test]
+fn compares_lists_that_contain_themselves() {
+    same(
+        r#"
+        manle a = [1]; push(a, a);
+        manle b = [1]; push(b, b);
+        manle c = [2]; push(c, c);
+        likh a == a, a == b, a == c, [a] == [b];
+        "#,
+        "true true false true\n",
+    );
+}
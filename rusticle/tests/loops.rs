@@ -0,0 +1,52 @@
+//! Checks `jabTak` and `for` loops, and leaving them with `ruk` and `agla`.
+
+mod common;
+
+use common::{parse_errors, same};
+
+#[test]
+fn agla_still_runs_the_for_increment() {
+    same(
+        r#"
+        for (manle i = 0; i < 6; i = i + 1) {
+            agar (i % 2 == 0) agla;
+            agar (i == 5) ruk;
+            likh i;
+        }
+        "#,
+        "1\n3\n",
+    );
+}
+
+#[test]
+fn ruk_and_agla_affect_the_innermost_loop() {
+    same(
+        r#"
+        manle i = 0;
+        jabTak (i < 3) {
+            i = i + 1;
+            manle j = 0;
+            jabTak (true) {
+                j = j + 1;
+                agar (j < i) agla;
+                ruk;
+            }
+            likh i, j;
+        }
+        "#,
+        "1 1\n2 2\n3 3\n",
+    );
+}
+
+#[test]
+fn ruk_and_agla_need_a_loop() {
+    let errors = parse_errors("ruk;\nfunctio f() { agla; }");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "[line 1] Error at 'ruk': Can't use 'ruk' outside of a loop.",
+            "[line 2] Error at 'agla': Can't use 'agla' outside of a loop.",
+        ]
+    );
+}
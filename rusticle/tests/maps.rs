@@ -0,0 +1,60 @@
+//! Checks map literals, keys, sharing and comparison.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn reads_adds_and_removes_entries() {
+    same(
+        r#"
+        manle person = #{"name": "Asha", "age": 31, true: "yes"};
+        likh person["name"];
+        person["age"] += 1;
+        person["city"] = "Pune";
+        likh keys(person), person["age"];
+        likh has(person, "zip"), remove(person, true), len(person);
+        "#,
+        "Asha\n[\"name\", \"age\", true, \"city\"] 32\nfalse yes 3\n",
+    );
+}
+
+#[test]
+fn equal_numbers_are_one_key() {
+    same("manle m = #{1: \"a\"}; m[1.0] = \"b\"; likh m, m[1];", "#{1: \"b\"} b\n");
+}
+
+#[test]
+fn rejects_missing_and_invalid_keys() {
+    same("likh #{}[\"zip\"];", "[line 1] RuntimeError: Key \"zip\" not found in map.");
+    same("likh #{[1]: 2};", "[line 1] RuntimeError: Map keys must be numbers, strings or booleans, but got list.");
+}
+
+#[test]
+fn compares_entries_in_any_order() {
+    same(
+        r#"likh #{"a": 1, "b": 2} == #{"b": 2, "a": 1}, #{"a": 1} == #{"a": 2}, #{"a": 1} == #{"b": 1};"#,
+        "true false false\n",
+    );
+}
+
+#[test]
+fn prints_a_map_that_contains_itself() {
+    same(
+        r#"manle m = #{"a": 1}; m["self"] = m; likh m;"#,
+        "#{\"a\": 1, \"self\": #{...}}\n",
+    );
+}
+
+#[test]
+fn compares_maps_that_contain_themselves() {
+    same(
+        r#"
+        manle a = #{}; a["s"] = a;
+        manle b = #{}; b["s"] = b;
+        manle c = #{}; c["t"] = c;
+        likh a == a, a == b, a == c, [a] == [b];
+        "#,
+        "true true false true\n",
+    );
+}
@@ -0,0 +1,42 @@
+//! Checks the built-in functions every program can call.
+
+mod common;
+
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use common::same;
+use rusticle::{Engine, Object};
+
+#[test]
+fn converts_and_inspects_values() {
+    same(
+        r#"
+        likh str(1.5) + "!", num(" 42 "), num("2.5");
+        likh type(1), type("s"), type(nil), type([]), type(#{}), type(len), type(clock() > 0);
+        likh len("héllo"), len([1, 2]), len(#{"a": 1}), len;
+        "#,
+        "1.5! 42 2.5\nint string nil list map function bool\n5 2 1 <native functio len>\n",
+    );
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    same("likh len(3);", "[line 1] RuntimeError: len() expects a list, map or string but got int.");
+    same("likh num(\"abc\");", "[line 1] RuntimeError: Can't convert 'abc' to a number.");
+    same("likh len();", "[line 1] RuntimeError: Expected 1 arguments but got 0.");
+}
+
+#[test]
+fn input_reads_lines_until_the_end() {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut engine = Engine::new();
+    engine.set_output(output.clone());
+    engine.set_input(Rc::new(RefCell::new(Cursor::new("31\r\nlast"))));
+
+    let value = engine.eval(r#"[num(input("Age? ")) + 1, input(), input()]"#).unwrap();
+    assert_eq!(value.to_string(), "[32, \"last\", nil]");
+    assert_eq!(String::from_utf8(output.borrow().clone()).unwrap(), "Age? ");
+    assert!(matches!(engine.eval("clock()").unwrap(), Object::Float(_)));
+}
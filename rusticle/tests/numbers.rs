@@ -0,0 +1,75 @@
+//! Checks integer and float arithmetic, and how the two kinds compare.
+
+mod common;
+
+use common::same;
+use rusticle::Object;
+
+#[test]
+fn keeps_integers_and_floats_apart() {
+    same(
+        "likh 7 / 2, 7 ~/ 2, 7 % 2, 2 ** 10, 1 + 2.0, type(1), type(1.0), 2.0;",
+        "3.5 3 1 1024 3.0 int float 2.0\n",
+    );
+    same("likh 9223372036854775807 + 1;", "[line 1] RuntimeError: Integer overflow.");
+}
+
+#[test]
+fn compares_integers_with_floats_exactly() {
+    same(
+        r#"
+        likh 1 == 1.0, 3 == 3.5, 9223372036854775807 == 9223372036854775808.0;
+        likh 9007199254740993 > 9007199254740992.0, 9007199254740993 == 9007199254740992.0;
+        likh 9007199254740992.0 < 9007199254740993, 3 < 3.5, -3 > -3.5, 3 <= 3.0, 3 < 3.0;
+        likh 9223372036854775807 < 9223372036854775808.0, 1 < 0.0 / 0.0, 1 > 0.0 / 0.0;
+        "#,
+        "true false false\ntrue false\ntrue true true true false\ntrue false false\n",
+    );
+}
+
+#[test]
+fn floats_outside_the_integers_are_their_own_keys() {
+    same(
+        r#"
+        manle m = #{9223372036854775807: "max", 2: "two"};
+        likh has(m, 9223372036854775808.0), m[2.0];
+        "#,
+        "false two\n",
+    );
+}
+
+#[test]
+fn package_locks_still_call_floats_numbers() {
+    let value: Object = serde_json::from_str(r#"{"Number": 1.5}"#).unwrap();
+    assert_eq!(value, Object::Float(1.5));
+}
+
+#[test]
+fn applies_the_full_operator_set() {
+    same(
+        "likh 7 % 3, -7 % 3, 2 ** 3, 2 ** -1, 7 ~/ 2, -7 ~/ 2, 7.5 ~/ 2;",
+        "1 2 8 0.5 3 -4 3.0\n",
+    );
+    same("likh 6 & 3, 6 | 3, 6 ^ 3, ~5, 1 << 4, -16 >> 2;", "2 7 5 -6 16 -4\n");
+    same("likh 1 ~/ 0;", "[line 1] RuntimeError: Division by zero.");
+    same("likh 1 << 64;", "[line 1] RuntimeError: Shift amount must be between 0 and 63.");
+}
+
+#[test]
+fn compound_assignment_updates_in_place() {
+    same(
+        r#"
+        manle n = 10;
+        n += 5; n -= 3; n *= 2; n /= 4;
+        likh n;
+        class Box { }
+        manle calls = 0;
+        manle box = Box();
+        box.v = 1;
+        functio get() { calls += 1; dede box; }
+        get().v += 5;
+        likh box.v, calls;
+        "#,
+        "6.0\n6 1\n",
+    );
+}
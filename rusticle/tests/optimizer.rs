@@ -0,0 +1,70 @@
+//! Checks what `--opt` may and may not rewrite. Every program must behave
+//! the same with and without it.
+
+mod common;
+
+use common::{check, same};
+use rusticle::parser::ast::{Expr, Object, Stmt};
+
+#[test]
+fn folds_literal_arithmetic() {
+    let statements = check("likh (1 + 2) * 3;", true).unwrap();
+    match &statements[..] {
+        [Stmt::Print { expressions }] => assert!(matches!(
+            &expressions[..],
+            [Expr::Literal { value: Object::Int(9) }]
+        )),
+        other => panic!("expected a print, got {:?}", other),
+    }
+}
+
+#[test]
+fn leaves_failing_folds_to_the_runtime() {
+    same("likh 1; likh 1 ~/ 0;", "1\n[line 1] RuntimeError: Division by zero.");
+    same("likh \"a\" - 1;", "[line 1] RuntimeError: Operands must be numbers.");
+}
+
+#[test]
+fn keeps_names_that_are_assigned_later() {
+    same(
+        r#"
+        manle x = 1;
+        functio bump() { x = x + 1; }
+        bump();
+        likh x;
+        "#,
+        "2\n",
+    );
+}
+
+#[test]
+fn keeps_names_declared_again_at_the_top_level() {
+    same(
+        r#"
+        manle x = 1;
+        functio show() { likh x; }
+        manle x = 2;
+        show();
+        "#,
+        "2\n",
+    );
+}
+
+#[test]
+fn keeps_parameters_that_shadow_a_constant() {
+    same(
+        r#"
+        manle x = 1;
+        functio f(x) { dede x; }
+        class A { m(x) { dede x * 10; } }
+        likh f(5), A().m(2), x;
+        "#,
+        "5 20 1\n",
+    );
+}
+
+#[test]
+fn reports_static_errors_in_dropped_branches() {
+    assert!(check("agar (false) { dede 1; }", true).is_err());
+    assert!(check("agar (false) { undef = 2; }", true).is_err());
+}
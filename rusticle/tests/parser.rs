@@ -0,0 +1,53 @@
+//! Checks what the parser accepts and where it reports what it doesn't.
+
+mod common;
+
+use common::{parse_errors, same};
+
+#[test]
+fn interpolates_expressions_into_strings() {
+    same(
+        r#"
+        manle n = 2;
+        likh "a${n}b${n * 2}c", "${"in${n}ner"}", "${#{"k": 1}["k"]}";
+        "#,
+        "a2b4c in2ner 1\n",
+    );
+}
+
+#[test]
+fn only_the_rest_of_its_string_ends_an_interpolation() {
+    // the `"b"` after the string can't stand in for its closing part
+    let errors = parse_errors(r#"likh "${"a" +}" "b";"#);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "[line 1] Error at '}': Expect expression.");
+    assert_eq!(errors[0].token.span().column, 14);
+}
+
+#[test]
+fn reports_a_missing_operand_at_the_closing_brace() {
+    let errors = parse_errors(r#"likh "x${1 +}y";"#);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "[line 1] Error at '}': Expect expression.");
+    assert_eq!(errors[0].token.span().column, 13);
+}
+
+#[test]
+fn recovers_to_report_every_syntax_error() {
+    let errors = parse_errors("likh 1\nmanle x = 2;\nlikh (3;\nlikh 4;");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "[line 2] Error at 'manle': Expect ';' after value.",
+            "[line 3] Error at ';': Expect ')' after expression.",
+        ]
+    );
+}
+
+#[test]
+fn stray_tokens_end_in_an_error_not_a_hang() {
+    for source in [")))", "}}", "functio f( { }", "class { }", "likh [1, 2"] {
+        assert!(!parse_errors(source).is_empty(), "{} parsed", source);
+    }
+}
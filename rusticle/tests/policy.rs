@@ -0,0 +1,49 @@
+//! Checks what the capability policy lets a program do.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rusticle::{Capability, Engine, Error, Policy};
+
+#[test]
+fn denies_everything_by_default() {
+    let policy = Policy::default();
+    for capability in [Capability::Read, Capability::Write, Capability::Net, Capability::Install] {
+        assert!(!policy.allows(capability), "{} allowed", capability);
+    }
+    assert!([Capability::Read, Capability::Install].iter().all(|c| Policy::allow_all().allows(*c)));
+}
+
+#[test]
+fn installing_also_needs_writing_and_the_network() {
+    let policy = Policy { install: true, write: true, ..Policy::default() };
+    assert!(!policy.allows(Capability::Install));
+    assert_eq!(
+        policy.check(Capability::Install).unwrap_err(),
+        "Installing packages is not allowed; run with --allow-write --allow-net to permit it."
+    );
+    assert!(Policy { net: true, ..policy }.allows(Capability::Install));
+}
+
+#[test]
+fn a_sandboxed_import_is_a_runtime_error() {
+    let mut engine = Engine::new();
+    match engine.eval(r#"import "add" from "addition";"#) {
+        Err(Error::Runtime(error)) => assert_eq!(
+            error.message,
+            "Reading files is not allowed; run with --allow-read to permit it."
+        ),
+        other => panic!("expected the import to be denied, got {:?}", other),
+    }
+}
+
+#[test]
+fn reading_lets_installed_packages_be_imported() {
+    // tests run in the crate directory, next to its rusticle.lock
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut engine = Engine::new();
+    engine.set_output(output.clone());
+    engine.set_policy(Policy { read: true, ..Policy::default() });
+    engine.eval(r#"import "add" from "addition"; add(2, 3);"#).unwrap();
+    assert_eq!(String::from_utf8(output.borrow().clone()).unwrap(), "5\n");
+}
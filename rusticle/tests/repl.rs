@@ -0,0 +1,36 @@
+//! Checks the REPL started when `rusticle` is run without a file.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs the REPL on `input`, returning what it printed and what it reported.
+fn repl(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rusticle"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn keeps_definitions_and_echoes_expressions() {
+    let (printed, reported) = repl("manle x = 2;\nx * 3\nfunctio f() {\n  dede x;\n}\nf()\n");
+    assert!(printed.ends_with("6\n2\n"), "{}", printed);
+    assert_eq!(reported, "");
+}
+
+#[test]
+fn carries_on_after_an_error() {
+    let (printed, reported) = repl("likh undefined;\nmanle y = 1 +;\n\"still here\"\n");
+    assert!(printed.ends_with("still here\n"), "{}", printed);
+    assert!(reported.contains("Undefined variable 'undefined'."), "{}", reported);
+    assert!(reported.contains("Expect expression."), "{}", reported);
+}
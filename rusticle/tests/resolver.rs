@@ -0,0 +1,64 @@
+//! Checks the static errors reported before a program runs, and that valid
+//! programs aren't rejected.
+
+mod common;
+
+use common::{check, cli, errors, same};
+
+#[test]
+fn natives_can_be_reassigned() {
+    same("clock = 1; len = 2; likh clock + len;", "3\n");
+}
+
+#[test]
+fn the_binary_knows_the_natives() {
+    let run = cli("natives-run", &[], "len = 2; likh len;");
+    assert_eq!(run.status.code(), Some(0), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "2\n");
+
+    let test = cli("natives-test", &["test"], r#"test "reassign" { clock = 1; assert_eq clock, 1; }"#);
+    assert_eq!(test.status.code(), Some(0), "{}", String::from_utf8_lossy(&test.stdout));
+}
+
+#[test]
+fn assigning_an_undeclared_name_is_an_error() {
+    let errors = check("undef = 1;", false).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "[line 1] Error at 'undef': Undefined variable 'undef'.");
+}
+
+#[test]
+fn closures_see_the_variable_in_scope_where_they_are_written() {
+    same(
+        r#"
+        manle a = "global";
+        {
+            functio show() { likh a; }
+            show();
+            manle a = "block";
+            show();
+        }
+        "#,
+        "global\nglobal\n",
+    );
+}
+
+#[test]
+fn reports_every_static_error_before_running() {
+    let source = r#"
+        likh "never printed";
+        { manle a = a; }
+        { manle b = 1; manle b = 2; }
+        dede 1;
+        functio f() { likh this; }
+    "#;
+    assert_eq!(
+        errors(source),
+        [
+            "[line 3] Error at 'a': Can't read local variable in its own initializer.",
+            "[line 4] Error at 'b': Already a variable with this name in this scope.",
+            "[line 5] Error at 'dede': Can't return from top-level code.",
+            "[line 6] Error at 'this': Can't use 'this' outside of a class.",
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+//! Checks that blocks nest their variables inside the enclosing scope.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn blocks_shadow_and_restore_outer_variables() {
+    same(
+        r#"
+        manle a = "global a";
+        manle b = "global b";
+        {
+            manle a = "outer a";
+            {
+                manle a = "inner a";
+                likh a, b;
+            }
+            likh a, b;
+        }
+        likh a, b;
+        "#,
+        "inner a global b\nouter a global b\nglobal a global b\n",
+    );
+}
+
+#[test]
+fn assignment_reaches_the_enclosing_scope() {
+    same(
+        "manle n = 1; { { n = n + 1; } n = n * 10; } likh n;",
+        "20\n",
+    );
+}
+
+#[test]
+fn block_variables_end_with_the_block() {
+    same("{ manle hidden = 1; } likh hidden;", "[line 1] RuntimeError: Undefined variable 'hidden'.");
+}
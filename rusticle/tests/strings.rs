@@ -0,0 +1,33 @@
+//! Checks string literals and the operators that work on strings.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn concatenates_and_compares_strings() {
+    same(
+        r#"likh "ab" + "cd", "a" < "b", "b" >= "ba", "x" == "x", "x" != "y", "1" == 1;"#,
+        "abcd true false true true false\n",
+    );
+}
+
+#[test]
+fn rejects_mixed_operands() {
+    same(r#"likh "a" + 1;"#, "[line 1] RuntimeError: Operands must be two numbers or two strings.");
+    same(r#"likh "a" < 1;"#, "[line 1] RuntimeError: Operands must be two numbers or two strings.");
+    same(r#"likh "a" * 2;"#, "[line 1] RuntimeError: Operands must be numbers.");
+}
+
+#[test]
+fn decodes_escape_sequences() {
+    same(
+        r#"likh "tab\tquote\" slash\\ dollar\${x} \u{e9}", len("a\nb");"#,
+        "tab\tquote\" slash\\ dollar${x} é 3\n",
+    );
+}
+
+#[test]
+fn strings_can_span_lines() {
+    same("likh \"one\ntwo\";\nlikh undefined;", "one\ntwo\n[line 3] RuntimeError: Undefined variable 'undefined'.");
+}
@@ -0,0 +1,44 @@
+//! Checks `test` blocks, assertions and the `rusticle test` subcommand.
+
+mod common;
+
+use common::{cli, errors, same};
+
+const SUITE: &str = r#"
+functio add(a, b) { dede a + b; }
+test "add works" {
+    assert add(2, 2) == 4;
+}
+test "broken" {
+    likh "shown";
+    assert_eq add(1, 2), "3";
+}
+"#;
+
+#[test]
+fn reports_failures_with_their_output() {
+    let run = cli("runner-all", &["test"], SUITE);
+    let printed = String::from_utf8_lossy(&run.stdout);
+    assert_eq!(run.status.code(), Some(1), "{}", printed);
+    assert!(printed.contains("test add works ... ok\ntest broken ... FAILED\n"), "{}", printed);
+    assert!(printed.contains("shown\n[line 8] RuntimeError: Assertion failed: left is 3 but right is \"3\"."));
+    assert!(printed.contains("test result: FAILED. 1 passed; 1 failed; 0 filtered out"));
+}
+
+#[test]
+fn filters_tests_by_name() {
+    let run = cli("runner-filter", &["test", "--filter", "add"], SUITE);
+    let printed = String::from_utf8_lossy(&run.stdout);
+    assert_eq!(run.status.code(), Some(0), "{}", printed);
+    assert!(printed.contains("test result: ok. 1 passed; 0 failed; 1 filtered out"), "{}", printed);
+}
+
+#[test]
+fn assertions_work_outside_tests() {
+    same("assert 1 < 2; likh \"fine\"; assert nil;", "fine\n[line 1] RuntimeError: Assertion failed.");
+    same("test \"skipped\" { likh 1; } likh 2;", "2\n");
+    assert_eq!(
+        errors("{ test \"nested\" { } }"),
+        ["[line 1] Error at 'nested': Test blocks must be at the top level."]
+    );
+}
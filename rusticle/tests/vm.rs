@@ -0,0 +1,119 @@
+//! Runs the same programs on the tree-walking interpreter and on the bytecode
+//! VM, which must agree on everything printed and on any runtime error.
+
+mod common;
+
+use common::same;
+
+#[test]
+fn closures_capture_variables() {
+    same(
+        r#"
+        functio counter() {
+            manle n = 0;
+            functio inc() { n = n + 1; dede n; }
+            dede inc;
+        }
+        manle c = counter();
+        c();
+        likh c(), counter()();
+
+        {
+            manle a = "outer";
+            functio show() { likh a; }
+            show();
+            a = "changed";
+            show();
+        }
+
+        functio outer() {
+            manle x = 1;
+            functio mid() { functio inner() { x = x + 1; dede x; } dede inner; }
+            dede mid();
+        }
+        manle f = outer();
+        f();
+        likh f();
+        "#,
+        "2 1\nouter\nchanged\n3\n",
+    );
+}
+
+#[test]
+fn each_iteration_gets_its_own_variables() {
+    same(
+        r#"
+        manle fs = [];
+        for (manle i = 0; i < 3; i = i + 1) {
+            manle j = i * 10;
+            functio get() { dede j; }
+            push(fs, get);
+        }
+        likh fs[0](), fs[1](), fs[2]();
+        "#,
+        "0 10 20\n",
+    );
+}
+
+#[test]
+fn ruk_and_agla_leave_nested_scopes() {
+    same(
+        r#"
+        for (manle i = 0; i < 10; i = i + 1) {
+            agar (i == 2) { agla; }
+            manle sq = i * i;
+            functio cap() { dede sq; }
+            {
+                manle inner = sq;
+                agar (i == 5) { ruk; }
+            }
+            likh i, cap();
+        }
+
+        manle count = 0;
+        for (manle a = 0; a < 3; a = a + 1) {
+            manle b = 0;
+            jabTak (b < 3) {
+                b = b + 1;
+                agar (b == 2) agla;
+                agar (a == 2) ruk;
+                count = count + 1;
+            }
+        }
+        likh count;
+        "#,
+        "0 0\n1 1\n3 9\n4 16\n4\n",
+    );
+}
+
+#[test]
+fn classes_call_up_with_super() {
+    same(
+        r#"
+        class A {
+            init(name) { this.name = name; }
+            greet() { dede "hi " + this.name; }
+            later() { functio f() { dede this.name; } dede f; }
+        }
+        class B < A {
+            init(name) { super.init(name + "!"); }
+            greet() { dede super.greet() + " from B"; }
+        }
+        manle b = B("bob");
+        likh b.greet(), b.later()(), b;
+        manle g = b.greet;
+        likh g();
+        likh A("x").init("y").name;
+        "#,
+        "hi bob! from B bob! B instance\nhi bob! from B\ny\n",
+    );
+}
+
+#[test]
+fn runtime_errors_stop_the_program() {
+    same("likh 1; likh 1 + nil; likh 2;", "1\n[line 1] RuntimeError: Operands must be two numbers or two strings.");
+    same("manle xs = [1]; likh xs[3];", "[line 1] RuntimeError: Index 3 is out of range for length 1.");
+    same("functio f(a) { dede a; }\nf(1, 2);", "[line 2] RuntimeError: Expected 1 arguments but got 2.");
+    same("class A { }\nlikh A().missing;", "[line 2] RuntimeError: Undefined property 'missing'.");
+    same("functio f() { f(); }\nf();", "[line 1] RuntimeError: Stack overflow.");
+}